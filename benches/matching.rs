@@ -0,0 +1,209 @@
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use globset::{GlobBuilder, GlobSetBuilder};
+use gitfilter::{Candidate, DirState, Matcher, Pattern, Scratch};
+
+/// Globs resembling those in real-world ignore files.
+fn globs(n: usize) -> Vec<String> {
+    (0..n)
+        .map(|i| match i % 6 {
+            0 => format!("**/*.ext{}", i),
+            1 => format!("**/name{}", i),
+            2 => format!("dir{}/**", i),
+            3 => format!("**/build{}/*.o", i),
+            4 => format!("**/prefix{}*", i),
+            _ => format!("src/gen{}/**/*.rs", i),
+        })
+        .collect()
+}
+
+/// Paths resembling those in a source tree, some of which match `globs(n)`.
+fn paths(n: usize) -> Vec<String> {
+    (0..n)
+        .map(|i| match i % 5 {
+            0 => format!("src/module{}/file{}.rs", i % 7, i),
+            1 => format!("dir{}/a/b/c.txt", i % 40),
+            2 => format!("target/debug/deps/lib{}.ext{}", i, i % 60),
+            3 => format!("a/b/name{}", i % 50),
+            _ => format!("docs/prefix{}_guide.md", i % 30),
+        })
+        .collect()
+}
+
+fn matcher(globs: &[String]) -> Matcher {
+    globs.iter().map(|g| Pattern::new(g, "").unwrap()).collect::<Result<_, _>>().unwrap()
+}
+
+fn bench_allocation(c: &mut Criterion) {
+    let (globs, paths) = (globs(300), paths(1000));
+    let matcher = matcher(&globs);
+
+    let mut group = c.benchmark_group("allocation");
+    group.bench_function("globset_matches", |b| {
+        // The previous approach: an allocating `GlobSet::matches()` followed
+        // by a walk over every pattern.
+        let mut builder = GlobSetBuilder::new();
+        for glob in &globs {
+            builder.add(GlobBuilder::new(glob).literal_separator(true).build().unwrap());
+        }
+
+        let set = builder.build().unwrap();
+        b.iter(|| {
+            for path in &paths {
+                let matches = set.matches(path);
+                let mut matched = false;
+                let mut m = matches.iter().peekable();
+                for i in 0..globs.len() {
+                    if m.next_if_eq(&&i).is_some() {
+                        matched = true;
+                    }
+                }
+
+                black_box(matched);
+            }
+        })
+    });
+
+    group.bench_function("matches", |b| b.iter(|| {
+        for path in &paths {
+            black_box(matcher.matches(path, false));
+        }
+    }));
+
+    group.bench_function("matches_candidate_into", |b| {
+        let candidates: Vec<_> = paths.iter().map(Candidate::new).collect();
+        let mut scratch = Scratch::new();
+        b.iter(|| {
+            for candidate in &candidates {
+                black_box(matcher.matches_candidate_into(candidate, false, &mut scratch));
+            }
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_evaluation(c: &mut Criterion) {
+    // Every path is matched by thousands of patterns, alternating between
+    // directory-only patterns, patterns, and exceptions.
+    let globs: Vec<String> = (0..5000)
+        .map(|i| match i % 3 {
+            0 => "*.rs/".to_string(),
+            1 => "*.rs".to_string(),
+            _ => "![a-z]*.rs".to_string(),
+        })
+        .collect();
+
+    let matcher = matcher(&globs);
+    let paths: Vec<String> = (0..1000).map(|i| format!("src/m{}/f{}.rs", i % 7, i)).collect();
+
+    let candidates: Vec<_> = paths.iter().map(Candidate::new).collect();
+
+    // Both strategies find every matching pattern into the same scratch
+    // space; they differ only in how the deciding pattern is picked.
+    let mut group = c.benchmark_group("evaluation");
+    group.bench_function("forward", |b| {
+        let mut scratch = Scratch::new();
+        b.iter(|| {
+            for candidate in &candidates {
+                let last = matcher.find_matches_into(candidate, false, &mut scratch).last();
+                black_box(matches!(last, Some(gitfilter::Match::Pattern(_))));
+            }
+        })
+    });
+
+    group.bench_function("reverse", |b| {
+        let mut scratch = Scratch::new();
+        b.iter(|| {
+            for candidate in &candidates {
+                black_box(matcher.matches_candidate_into(candidate, false, &mut scratch));
+            }
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_roots(c: &mut Criterion) {
+    // A monorepo: the same ignore file in every service. A path under one
+    // service should only pay for the patterns rooted at that service.
+    let mut group = c.benchmark_group("roots");
+    for services in [1, 10, 100] {
+        let matcher: Matcher = (0..services)
+            .flat_map(|s| globs(60).into_iter().map(move |g| (g, format!("services/s{}", s))))
+            .map(|(g, root)| Pattern::new(&g, root).unwrap())
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let paths: Vec<String> = paths(1000).iter().map(|p| format!("services/s0/{}", p)).collect();
+        group.bench_function(format!("{}", services), |b| b.iter(|| {
+            for path in &paths {
+                black_box(matcher.matches(path, false));
+            }
+        }));
+    }
+
+    group.finish();
+}
+
+fn bench_walk(c: &mut Criterion) {
+    // A tree four levels deep, matched path by path or by descending.
+    const NAMES: &[&str] = &["src", "a", "b", "name6", "dir2", "lib.ext0", "build3", "x.o", "gen5"];
+    let matcher = matcher(&globs(300));
+
+    let mut group = c.benchmark_group("walk");
+    group.bench_function("matches", |b| b.iter(|| {
+        fn walk(matcher: &Matcher, dir: &str, depth: usize) {
+            for name in NAMES {
+                let path = format!("{}/{}", dir, name);
+                black_box(matcher.matches(&path[1..], depth > 1));
+                if depth > 1 {
+                    walk(matcher, &path, depth - 1);
+                }
+            }
+        }
+
+        walk(&matcher, "", 4)
+    }));
+
+    group.bench_function("descend", |b| b.iter(|| {
+        fn walk(matcher: &Matcher, dir: &DirState, depth: usize) {
+            for name in NAMES {
+                let state = matcher.descend(dir, name, depth > 1);
+                black_box(state.is_match());
+                if depth > 1 && !state.is_exhausted() {
+                    walk(matcher, &state, depth - 1);
+                }
+            }
+        }
+
+        walk(&matcher, &matcher.dir_state(""), 4)
+    }));
+
+    group.finish();
+}
+
+fn bench_update(c: &mut Criterion) {
+    // Replacing one pattern in a large ignore file, by recompiling the one
+    // chunk holding it or by rebuilding the matcher. Both are linear in the
+    // number of patterns; `replace` only saves compiling the other globs.
+    let globs = globs(3000);
+    let mut group = c.benchmark_group("update");
+    group.bench_function("rebuild", |b| b.iter(|| {
+        let mut globs = globs.clone();
+        globs[1500] = "**/edited*".into();
+        black_box(matcher(&globs))
+    }));
+
+    let mut matcher = matcher(&globs);
+    let id = matcher.id(1500).unwrap();
+    group.bench_function("replace", |b| b.iter(|| {
+        black_box(matcher.replace(id, Pattern::new("**/edited*", "").unwrap()).unwrap());
+    }));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_allocation, bench_evaluation, bench_roots, bench_walk, bench_update);
+criterion_main!(benches);
@@ -0,0 +1,90 @@
+use std::path::{Component, Path, PathBuf};
+
+use crate::{Error, Match, Matcher, PathExt};
+
+/// How a [`BasedMatcher`] resolves the paths it's queried with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathMode {
+    /// Paths are matched relative to the base. Absolute paths are made
+    /// relative by stripping the base. Use this for patterns with an empty
+    /// root.
+    Relative,
+    /// Paths are matched as absolute paths. Relative paths are resolved
+    /// against the base. Use this for patterns rooted at the base.
+    Absolute,
+}
+
+/// A [`Matcher`] queried with paths relative to, or below, a base directory.
+///
+/// Unlike [`Matcher::matches()`], which matches any path as given, queries
+/// fail with [`Error::OutsideBase`] for paths that aren't below the base.
+#[derive(Debug, Clone)]
+pub struct BasedMatcher<'m> {
+    matcher: &'m Matcher,
+    base: PathBuf,
+    mode: PathMode,
+}
+
+impl Matcher {
+    /// Returns a view of this matcher that resolves the paths it's queried
+    /// with against `base`, as `mode` says.
+    pub fn based<P: AsRef<Path>>(&self, base: P, mode: PathMode) -> BasedMatcher<'_> {
+        BasedMatcher { matcher: self, base: base.as_ref().to_path_buf(), mode }
+    }
+}
+
+impl<'m> BasedMatcher<'m> {
+    /// Returns the path `path` is matched as, without any `.` or `..`
+    /// components, or an error if it isn't below the base.
+    pub fn resolve<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf, Error> {
+        let path = path.as_ref();
+        let outside = || Error::OutsideBase {
+            path: path.to_normalized_string_lossy().into(),
+            base: self.base.to_normalized_string_lossy().into(),
+        };
+
+        // The path relative to the base, which it must not leave. Paths are
+        // compared by component, so the base is only a prefix of the path if
+        // it ends at a component boundary.
+        let relative = match path.has_root() {
+            true if !self.base.has_root() => return Err(outside()),
+            true => Path::strip_prefix(path, &self.base).map_err(|_| outside())?,
+            false => path,
+        };
+
+        let mut resolved = PathBuf::new();
+        for component in relative.components() {
+            match component {
+                Component::Normal(name) => resolved.push(name),
+                Component::ParentDir if resolved.pop() => {}
+                Component::ParentDir => return Err(outside()),
+                Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+            }
+        }
+
+        match self.mode {
+            PathMode::Relative => Ok(resolved),
+            PathMode::Absolute => Ok(self.base.join(resolved)),
+        }
+    }
+
+    /// Like [`Matcher::matches()`] for the path `path` resolves to.
+    pub fn matches<P: AsRef<Path>>(&self, path: P, is_dir: bool) -> Result<bool, Error> {
+        Ok(self.matcher.matches(self.resolve(path)?, is_dir))
+    }
+
+    /// Like [`Matcher::find_match()`] for the path `path` resolves to.
+    pub fn find_match<P: AsRef<Path>>(&self, path: P, is_dir: bool) -> Result<Option<Match<'m>>, Error> {
+        Ok(self.matcher.find_match(self.resolve(path)?, is_dir))
+    }
+
+    /// The base paths are resolved against.
+    pub fn base(&self) -> &Path {
+        &self.base
+    }
+
+    /// The underlying matcher.
+    pub fn matcher(&self) -> &'m Matcher {
+        self.matcher
+    }
+}
@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::fmt::Write;
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{Document, Error, Matcher, PatternSet};
+
+/// The name of the ignore file read from every directory.
+const IGNORE_FILE: &str = ".gitignore";
+
+/// The first line of a saved cache. Caches with any other are discarded.
+const HEADER: &str = "gitfilter status cache v1";
+
+/// The ignore status of an entry in a tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Status {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    /// Whether the entry is matched by the ignore files in its directory and
+    /// the directories above it, or is below an ignored directory. As in git,
+    /// an entry below an ignored directory can't be re-included.
+    pub ignored: bool,
+}
+
+/// A point in time, as seconds and nanoseconds since the epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Time(u64, u32);
+
+/// When a file was last modified and its size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp {
+    modified: Time,
+    size: u64,
+}
+
+/// The cached status of a directory's entries.
+#[derive(Debug, Clone)]
+struct CachedDir {
+    /// The stamp of the directory, which changes when entries are added or
+    /// removed.
+    stamp: Stamp,
+    /// The stamp of the directory's ignore file, if it has one.
+    ignore: Option<Stamp>,
+    /// `(name, is_dir, ignored)` for each entry, sorted by name.
+    entries: Vec<(OsString, bool, bool)>,
+}
+
+/// A persistent cache of the ignore status of every entry in a tree, with the
+/// rules read from the `.gitignore` file in each directory.
+///
+/// Like git's untracked cache, the cache records the modification time of
+/// each directory and the modification time and size of each ignore file. A
+/// directory's entries are only listed and matched again if the directory
+/// changed or any ignore file in it or above it did, so repeated queries on
+/// an unchanged tree cost one `stat` per directory.
+///
+/// Only statuses are cached, not the matchers that produced them: compiled
+/// globs can't be saved, so a directory that's matched again has its ignore
+/// files, and those above it, read and compiled again.
+#[derive(Debug)]
+pub struct StatusCache {
+    root: PathBuf,
+    /// When the last scan started. Anything modified since, or around then,
+    /// may have changed again without changing its stamp, so isn't trusted.
+    scanned: Option<Time>,
+    /// Directory, relative to `root` -> its cached entries.
+    dirs: HashMap<PathBuf, CachedDir>,
+    refreshed: usize,
+}
+
+/// The ignore files that apply to a directory.
+#[derive(Clone)]
+struct Inputs {
+    files: Vec<PathBuf>,
+    /// Whether any of `files` changed since they were cached.
+    changed: bool,
+    /// The matcher compiled from `files`, once it's needed.
+    matcher: Option<Rc<Matcher>>,
+}
+
+impl Time {
+    fn of(time: SystemTime) -> Time {
+        let duration = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        Time(duration.as_secs(), duration.subsec_nanos())
+    }
+}
+
+impl Stamp {
+    fn of(metadata: &Metadata) -> io::Result<Stamp> {
+        Ok(Stamp { modified: Time::of(metadata.modified()?), size: metadata.len() })
+    }
+}
+
+impl Inputs {
+    fn matcher(&mut self) -> Result<Rc<Matcher>, Error> {
+        if let Some(matcher) = &self.matcher {
+            return Ok(matcher.clone());
+        }
+
+        let mut set = PatternSet::new();
+        for file in &self.files {
+            let text = fs::read_to_string(file)?;
+            let dir = file.parent().expect("ignore files are in a directory");
+            set.extend(Document::parse(&text, dir)?.to_pattern_set().into_iter());
+        }
+
+        let matcher = Rc::new(set.into_matcher()?);
+        self.matcher = Some(matcher.clone());
+        Ok(matcher)
+    }
+}
+
+impl StatusCache {
+    /// Creates an empty cache for the tree rooted at `root`.
+    pub fn new<P: AsRef<Path>>(root: P) -> StatusCache {
+        StatusCache {
+            root: root.as_ref().to_path_buf(),
+            scanned: None,
+            dirs: HashMap::new(),
+            refreshed: 0,
+        }
+    }
+
+    /// Loads the cache for the tree rooted at `root` saved in `file`. If
+    /// there's no such file, or it doesn't hold a valid cache for `root`, the
+    /// cache is empty.
+    pub fn load<P: AsRef<Path>, F: AsRef<Path>>(root: P, file: F) -> Result<StatusCache, Error> {
+        let mut cache = StatusCache::new(root);
+        let text = match fs::read_to_string(file) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(cache),
+            Err(e) => return Err(e.into()),
+        };
+
+        if let Some((scanned, dirs)) = parse(&text, &cache.root) {
+            cache.scanned = Some(scanned);
+            cache.dirs = dirs;
+        }
+
+        Ok(cache)
+    }
+
+    /// Saves the cache to `file`, replacing it.
+    pub fn save<F: AsRef<Path>>(&self, file: F) -> Result<(), Error> {
+        let file = file.as_ref();
+        let mut text = format!("{}\n{}\n", HEADER, escape(self.root.as_os_str()));
+        if let Some(Time(secs, nanos)) = self.scanned {
+            let _ = writeln!(text, "{} {}", secs, nanos);
+        }
+
+        let mut dirs: Vec<_> = self.dirs.iter().collect();
+        dirs.sort_by(|a, b| a.0.cmp(b.0));
+        for (path, dir) in dirs {
+            let _ = write!(text, "dir {} {}", stamp(Some(dir.stamp)), stamp(dir.ignore));
+            let _ = writeln!(text, " {}", escape(path.as_os_str()));
+            for (name, is_dir, ignored) in &dir.entries {
+                let kind = if *is_dir { 'd' } else { 'f' };
+                let status = if *ignored { 'i' } else { '-' };
+                let _ = writeln!(text, "{}{} {}", kind, status, escape(name));
+            }
+        }
+
+        // Write and rename so that readers never see a partial cache.
+        let mut temporary = file.as_os_str().to_owned();
+        temporary.push(".tmp");
+        fs::write(&temporary, text)?;
+        fs::rename(&temporary, file)?;
+        Ok(())
+    }
+
+    /// Returns the status of every entry below the root, in the same order
+    /// as a depth-first walk visiting entries sorted by name. Symbolic links
+    /// are not followed, and `.git` directories are skipped.
+    ///
+    /// Only directories that changed, or whose ignore files changed, since
+    /// the last call are listed and matched again.
+    pub fn status(&mut self) -> Result<Vec<Status>, Error> {
+        let started = Time::of(SystemTime::now());
+        let inputs = Inputs { files: vec![], changed: false, matcher: None };
+        let (mut fresh, mut statuses) = (HashMap::new(), vec![]);
+        self.refreshed = 0;
+        self.visit(Path::new(""), false, &inputs, &mut fresh, &mut statuses)?;
+        self.dirs = fresh;
+        self.scanned = Some(started);
+        Ok(statuses)
+    }
+
+    /// The number of directories whose entries were listed and matched again
+    /// in the last call to [`StatusCache::status()`].
+    pub fn refreshed(&self) -> usize {
+        self.refreshed
+    }
+
+    /// Visits the directory `path`, which is itself ignored if `ignored`.
+    fn visit(
+        &mut self,
+        path: &Path,
+        ignored: bool,
+        parent: &Inputs,
+        fresh: &mut HashMap<PathBuf, CachedDir>,
+        statuses: &mut Vec<Status>,
+    ) -> Result<(), Error> {
+        let dir = self.root.join(path);
+        let stamp = Stamp::of(&fs::symlink_metadata(&dir)?)?;
+        let ignore_file = dir.join(IGNORE_FILE);
+        let ignore = match fs::symlink_metadata(&ignore_file) {
+            Ok(metadata) => Some(Stamp::of(&metadata)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        // Timestamps are coarser than the clock, so anything modified in the
+        // same second as the last scan may have changed without its stamp.
+        let trusted = |stamp: &Stamp| self.scanned.is_some_and(|scanned| stamp.modified.0 < scanned.0);
+        let cached = self.dirs.remove(path);
+        let mut inputs = parent.clone();
+        inputs.changed |= cached.as_ref().map(|cached| cached.ignore) != Some(ignore)
+            || ignore.is_some_and(|ignore| !trusted(&ignore));
+
+        if ignore.is_some() {
+            inputs.files.push(ignore_file);
+            inputs.matcher = None;
+        }
+
+        // Whether the directory is ignored only changes with the ignore files
+        // above it, so entries cached while it was are still valid.
+        let entries = match cached {
+            Some(cached) if !inputs.changed && cached.stamp == stamp && trusted(&stamp) => cached.entries,
+            _ => {
+                self.refreshed += 1;
+                let mut children = fs::read_dir(&dir)?.collect::<io::Result<Vec<_>>>()?;
+                children.sort_by_key(|entry| entry.file_name());
+
+                let mut entries = Vec::with_capacity(children.len());
+                for child in children {
+                    let is_dir = child.file_type()?.is_dir();
+                    if is_dir && child.file_name() == ".git" {
+                        continue;
+                    }
+
+                    let ignored = ignored || inputs.matcher()?.matches(child.path(), is_dir);
+                    entries.push((child.file_name(), is_dir, ignored));
+                }
+
+                entries
+            }
+        };
+
+        for (name, is_dir, ignored) in &entries {
+            statuses.push(Status { path: dir.join(name), is_dir: *is_dir, ignored: *ignored });
+            if *is_dir {
+                self.visit(&path.join(name), *ignored, &inputs, fresh, statuses)?;
+            }
+        }
+
+        fresh.insert(path.to_path_buf(), CachedDir { stamp, ignore, entries });
+        Ok(())
+    }
+}
+
+fn stamp(stamp: Option<Stamp>) -> String {
+    match stamp {
+        Some(Stamp { modified: Time(secs, nanos), size }) => format!("{}.{}.{}", secs, nanos, size),
+        None => "-".into(),
+    }
+}
+
+fn parse_stamp(text: &str) -> Option<Option<Stamp>> {
+    if text == "-" {
+        return Some(None);
+    }
+
+    let mut parts = text.split('.').map(|part| part.parse::<u64>().ok());
+    let (secs, nanos, size) = (parts.next()??, parts.next()??, parts.next()??);
+    let modified = Time(secs, nanos.try_into().ok()?);
+    Some(Some(Stamp { modified, size }))
+}
+
+/// Parses a saved cache for `root`, returning `None` if it's invalid.
+fn parse(text: &str, root: &Path) -> Option<(Time, HashMap<PathBuf, CachedDir>)> {
+    let mut lines = text.lines();
+    if lines.next()? != HEADER || unescape(lines.next()?)? != root.as_os_str() {
+        return None;
+    }
+
+    let (secs, nanos) = lines.next()?.split_once(' ')?;
+    let scanned = Time(secs.parse().ok()?, nanos.parse().ok()?);
+
+    let mut dirs = HashMap::new();
+    let mut current: Option<(PathBuf, CachedDir)> = None;
+    for line in lines {
+        let (kind, rest) = line.split_once(' ')?;
+        if kind == "dir" {
+            let mut fields = rest.splitn(3, ' ');
+            let stamp = parse_stamp(fields.next()?)??;
+            let ignore = parse_stamp(fields.next()?)?;
+            let path = PathBuf::from(unescape(fields.next()?)?);
+            let dir = CachedDir { stamp, ignore, entries: vec![] };
+            dirs.extend(current.replace((path, dir)));
+            continue;
+        }
+
+        let (is_dir, ignored) = match kind {
+            "f-" => (false, false),
+            "fi" => (false, true),
+            "d-" => (true, false),
+            "di" => (true, true),
+            _ => return None,
+        };
+
+        current.as_mut()?.1.entries.push((unescape(rest)?, is_dir, ignored));
+    }
+
+    dirs.extend(current);
+    Some((scanned, dirs))
+}
+
+/// Encodes `string` as printable ASCII without spaces, percent-encoding all
+/// other bytes.
+fn escape(string: &OsStr) -> String {
+    let mut escaped = String::new();
+    for &byte in crate::pathext::os_str_bytes(string).iter() {
+        match byte {
+            b'!'..=b'~' if byte != b'%' => escaped.push(byte as char),
+            _ => { let _ = write!(escaped, "%{:02X}", byte); }
+        }
+    }
+
+    escaped
+}
+
+/// Decodes a string encoded by [`escape()`].
+fn unescape(string: &str) -> Option<OsString> {
+    let (mut bytes, mut rest) = (vec![], string.as_bytes());
+    while let Some((&byte, tail)) = rest.split_first() {
+        match byte {
+            b'%' => {
+                let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+
+    crate::pathext::os_string_from_bytes(bytes)
+}
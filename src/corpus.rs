@@ -0,0 +1,114 @@
+use std::collections::BTreeSet;
+
+use crate::{Matcher, Pattern};
+
+/// How [`expand()`] instantiates each kind of wildcard.
+struct Expansion {
+    star: &'static str,
+    globstar: &'static str,
+    last_alternative: bool,
+}
+
+const EXPANSIONS: &[Expansion] = &[
+    Expansion { star: "", globstar: "", last_alternative: false },
+    Expansion { star: "x", globstar: "d/e", last_alternative: true },
+    Expansion { star: "x.y", globstar: "d", last_alternative: false },
+];
+
+/// Instantiates `glob` into a concrete path that the glob is likely, but not
+/// guaranteed, to match.
+fn expand(glob: &str, expansion: &Expansion) -> String {
+    let mut path = String::new();
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => path.extend(chars.next()),
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                path.push_str(expansion.globstar);
+                if expansion.globstar.is_empty() && chars.peek() == Some(&'/') {
+                    chars.next();
+                }
+            }
+            '*' => path.push_str(expansion.star),
+            '?' => path.push('x'),
+            '[' => {
+                let negated = matches!(chars.peek(), Some('!' | '^'));
+                if negated {
+                    chars.next();
+                }
+
+                let mut first = None;
+                for c in chars.by_ref() {
+                    if c == ']' && first.is_some() {
+                        break;
+                    }
+
+                    first.get_or_insert(c);
+                }
+
+                match (negated, first) {
+                    (false, Some(c)) => path.push(c),
+                    _ => path.push('~'),
+                }
+            }
+            '{' => {
+                let mut alternatives = vec![String::new()];
+                for c in chars.by_ref() {
+                    match c {
+                        '}' => break,
+                        ',' => alternatives.push(String::new()),
+                        c => alternatives.last_mut().unwrap().push(c),
+                    }
+                }
+
+                let alternative = match expansion.last_alternative {
+                    true => alternatives.pop(),
+                    false => alternatives.into_iter().next(),
+                };
+
+                path.push_str(&alternative.unwrap_or_default());
+            }
+            c => path.push(c),
+        }
+    }
+
+    path
+}
+
+/// Generates a corpus of paths that exercise `patterns`: paths each pattern
+/// is likely to match, near misses, and their parents and children.
+pub(crate) fn sample_paths<'a, I>(patterns: I) -> BTreeSet<String>
+    where I: IntoIterator<Item = &'a Pattern>
+{
+    let mut corpus = BTreeSet::new();
+    for pattern in patterns {
+        for expansion in EXPANSIONS {
+            let path = expand(pattern.glob.glob(), expansion);
+            if let Some((parent, _)) = path.rsplit_once('/') {
+                corpus.insert(parent.to_string());
+            }
+
+            if !path.starts_with('/') {
+                corpus.insert(format!("sub/{}", path));
+            }
+
+            corpus.insert(format!("{}/c", path));
+            corpus.insert(format!("{}x", path));
+            corpus.insert(path);
+        }
+    }
+
+    corpus.remove("");
+    corpus
+}
+
+/// Returns the first path in the corpus, along with its directory-ness, that
+/// `a` and `b` disagree on.
+pub(crate) fn first_difference<'p, I>(a: &Matcher, b: &Matcher, corpus: I) -> Option<(&'p str, bool)>
+    where I: IntoIterator<Item = &'p String>
+{
+    corpus.into_iter()
+        .flat_map(|path| [(path.as_str(), false), (path.as_str(), true)])
+        .find(|&(path, is_dir)| a.matches(path, is_dir) != b.matches(path, is_dir))
+}
@@ -0,0 +1,106 @@
+use std::fmt;
+use std::path::Path;
+
+use crate::format::canonical;
+use crate::memo::Exclusions;
+use crate::{walk, Error, Matcher, Pattern};
+
+/// How often each pattern in a [`Matcher`] matches a set of paths.
+#[derive(Debug)]
+pub struct Coverage<'a> {
+    matcher: &'a Matcher,
+    hits: Vec<usize>,
+    decisive: Vec<usize>,
+}
+
+/// The coverage of a single pattern.
+#[derive(Debug, Clone, Copy)]
+pub struct PatternCoverage<'a> {
+    pub pattern: &'a Pattern,
+    /// The number of paths the pattern truly matched.
+    pub hits: usize,
+    /// The number of paths for which the pattern was the last to match and
+    /// thus decided whether the path is matched.
+    pub decisive: usize,
+}
+
+impl<'a> Coverage<'a> {
+    /// Computes the coverage of `matcher` over `paths`. Like git, patterns
+    /// aren't matched against paths below a directory the matcher excludes,
+    /// so such paths count towards no pattern.
+    pub fn new<I, P>(matcher: &'a Matcher, paths: I) -> Coverage<'a>
+        where I: IntoIterator<Item = (P, bool)>, P: AsRef<Path>
+    {
+        Coverage::below(matcher, paths, Path::new(""))
+    }
+
+    /// Like [`Coverage::new()`], for paths below `top`.
+    fn below<I, P>(matcher: &'a Matcher, paths: I, top: &Path) -> Coverage<'a>
+        where I: IntoIterator<Item = (P, bool)>, P: AsRef<Path>
+    {
+        let mut exclusions = Exclusions::new(matcher, top);
+        let mut hits = vec![0; matcher.len()];
+        let mut decisive = vec![0; matcher.len()];
+        for (path, is_dir) in paths {
+            let path = path.as_ref();
+            if exclusions.excluding_parent(path).is_some() {
+                continue;
+            }
+
+            let mut last = None;
+            for i in matcher.true_matches(path, is_dir) {
+                hits[i] += 1;
+                last = Some(i);
+            }
+
+            if let Some(i) = last {
+                decisive[i] += 1;
+            }
+        }
+
+        Coverage { matcher, hits, decisive }
+    }
+
+    /// Computes the coverage of `matcher` over every entry in the directory
+    /// tree rooted at `dir`. Paths are `dir` joined with the relative path of
+    /// the entry.
+    pub fn tree<P: AsRef<Path>>(matcher: &'a Matcher, dir: P) -> Result<Self, Error> {
+        let dir = dir.as_ref();
+        Ok(Coverage::below(matcher, walk::walk(dir)?, dir))
+    }
+
+    /// Iterates over the coverage of every pattern, in order.
+    pub fn iter(&self) -> impl Iterator<Item = PatternCoverage<'a>> + '_ {
+        self.matcher.iter()
+            .zip(self.hits.iter().zip(&self.decisive))
+            .map(|(pattern, (&hits, &decisive))| PatternCoverage { pattern, hits, decisive })
+    }
+
+    /// Iterates over the patterns that never matched any path.
+    pub fn unused(&self) -> impl Iterator<Item = &'a Pattern> + '_ {
+        self.iter().filter(|c| c.hits == 0).map(|c| c.pattern)
+    }
+
+    /// Iterates over the patterns that matched some path but were always
+    /// overridden by a later pattern, and so never decided any path.
+    pub fn overridden(&self) -> impl Iterator<Item = &'a Pattern> + '_ {
+        self.iter().filter(|c| c.hits > 0 && c.decisive == 0).map(|c| c.pattern)
+    }
+}
+
+impl fmt::Display for Coverage<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for coverage in self.iter() {
+            let note = match (coverage.hits, coverage.decisive) {
+                (0, _) => "  (unused)",
+                (_, 0) => "  (overridden)",
+                _ => "",
+            };
+
+            let line = canonical(coverage.pattern);
+            writeln!(f, "{:>8} {:>8}  {}{}", coverage.hits, coverage.decisive, line, note)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use globset::{GlobMatcher, GlobSet, GlobSetBuilder};
+
+use crate::engine::Scratch;
+use crate::pattern::{compile_glob, is_literal};
+use crate::Pattern;
+
+/// The match state of a directory entry, produced by
+/// [`Matcher::descend()`](crate::Matcher::descend) from the state of its
+/// parent directory.
+///
+/// A state records which patterns could still match the entry's descendants,
+/// and how far along each of them is, so that descending into a child only
+/// needs to look at the child's name. A state is only meaningful to the
+/// [`Matcher`](crate::Matcher) that produced it.
+#[derive(Debug, Clone)]
+pub struct DirState {
+    /// The path of the entry, sharing its parent's. Only kept, and consulted,
+    /// if some pattern can't be matched one component at a time.
+    path: Option<Arc<PathNode>>,
+    /// `(pattern, component)`: the pattern has matched the path up to its
+    /// `component`th component, which is next to be matched.
+    pub(crate) positions: Vec<(usize, usize)>,
+    pub(crate) matched: bool,
+    pub(crate) exhausted: bool,
+}
+
+impl DirState {
+    /// Returns `true` if the entry is matched, exactly as
+    /// [`Matcher::matches()`](crate::Matcher::matches) would report for its
+    /// path.
+    pub fn is_match(&self) -> bool {
+        self.matched
+    }
+
+    /// Returns `true` if no pattern can match any path below the entry, and
+    /// so no descendant is matched.
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+}
+
+/// A path: the name of its last component and the path of its parent.
+#[derive(Debug)]
+struct PathNode {
+    parent: Option<Arc<PathNode>>,
+    name: Box<[u8]>,
+}
+
+impl PathNode {
+    /// Writes the path into `buf`, replacing what's there.
+    fn write(&self, buf: &mut Vec<u8>) {
+        match &self.parent {
+            Some(parent) => {
+                parent.write(buf);
+                buf.push(b'/');
+            }
+            None => buf.clear(),
+        }
+
+        buf.extend_from_slice(&self.name);
+    }
+}
+
+/// One `/`-separated component of a glob.
+#[derive(Debug)]
+enum Component {
+    /// `**`: any number of path components.
+    Recursive,
+    Literal(Box<[u8]>),
+    Glob(GlobMatcher),
+}
+
+impl Component {
+    fn compile(glob: &str) -> Option<Component> {
+        if glob == "**" {
+            return Some(Component::Recursive);
+        } else if is_literal(glob) {
+            return Some(Component::Literal(glob.as_bytes().into()));
+        }
+
+        // A negated or ranged class may match `/`, so the pattern can't be
+        // split into components.
+        if glob.split_once('[').is_some_and(|(_, class)| class.contains(['!', '^', '-'])) {
+            return None;
+        }
+
+        // Components that don't compile on their own, like `{a` from `{a,b/c}`,
+        // were split in the middle of something.
+        let glob = compile_glob(glob).ok()?;
+        Some(Component::Glob(glob.compile_matcher()))
+    }
+
+    fn is_match(&self, name: &[u8]) -> bool {
+        match self {
+            Component::Recursive => true,
+            Component::Literal(literal) => **literal == *name,
+            Component::Glob(glob) => glob.is_match_candidate(&globset::Candidate::from_bytes(name)),
+        }
+    }
+}
+
+/// Patterns compiled into sequences of components, matched against a path
+/// one component at a time.
+///
+/// Patterns of the form `**/{c}/...` are live in every directory; they're
+/// kept out of the states and bucketed by `{c}` instead. Patterns that can't
+/// be split into components are matched against the full path.
+#[derive(Debug, Default)]
+pub(crate) struct Automaton {
+    /// Pattern index -> its components, or empty if it's opaque.
+    programs: Vec<Vec<Component>>,
+    /// The positions of the empty path.
+    start: Vec<(usize, usize)>,
+    /// Literal `{c}` -> floating patterns of the form `**/{c}/...`.
+    floating_names: HashMap<Box<[u8]>, Vec<usize>>,
+    /// The remaining floating patterns' `{c}`s.
+    floating_globs: GlobSet,
+    /// Index into `floating_globs` -> index of the pattern.
+    floating_glob_indices: Vec<usize>,
+    /// Patterns that can't be split into components.
+    opaque: Vec<(usize, GlobMatcher)>,
+}
+
+impl Automaton {
+    pub fn new(patterns: &[Pattern]) -> Automaton {
+        let (mut automaton, mut start) = (Automaton::default(), vec![]);
+        for (i, pattern) in patterns.iter().enumerate() {
+            let glob = pattern.glob.glob();
+            // Only the first component, of an absolute path, may be empty.
+            let program = glob.split('/')
+                .enumerate()
+                .map(|(n, c)| if n > 0 && c.is_empty() { None } else { Component::compile(c) })
+                .collect::<Option<Vec<_>>>();
+
+            let Some(program) = program else {
+                automaton.opaque.push((i, pattern.glob.compile_matcher()));
+                automaton.programs.push(vec![]);
+                continue;
+            };
+
+            let floating = match &program[..] {
+                [Component::Recursive, Component::Literal(name), ..] => {
+                    automaton.floating_names.entry(name.clone()).or_default().push(i);
+                    true
+                }
+                [Component::Recursive, Component::Glob(_), ..] => {
+                    automaton.floating_glob_indices.push(i);
+                    true
+                }
+                _ => false,
+            };
+
+            automaton.programs.push(program);
+            if !floating {
+                automaton.enter(i, 0, &mut start, &mut vec![]);
+            }
+        }
+
+        let mut builder = GlobSetBuilder::new();
+        for &i in &automaton.floating_glob_indices {
+            if let Component::Glob(glob) = &automaton.programs[i][1] {
+                builder.add(glob.glob().clone());
+            }
+        }
+
+        // If they don't fit in one set, match them against the full path.
+        match builder.build() {
+            Ok(set) => automaton.floating_globs = set,
+            Err(_) => for i in std::mem::take(&mut automaton.floating_glob_indices) {
+                automaton.opaque.push((i, patterns[i].glob.compile_matcher()));
+                automaton.programs[i].clear();
+            }
+        }
+
+        automaton.start = start;
+        automaton
+    }
+
+    /// The state of the empty path.
+    pub fn start(&self) -> DirState {
+        DirState {
+            path: None,
+            positions: self.start.clone(),
+            matched: false,
+            exhausted: self.is_exhausted(&self.start),
+        }
+    }
+
+    fn is_exhausted(&self, positions: &[(usize, usize)]) -> bool {
+        positions.is_empty()
+            && self.floating_names.is_empty()
+            && self.floating_glob_indices.is_empty()
+            && self.opaque.is_empty()
+    }
+
+    /// Iterates over the patterns that may match some path below `dir`, each
+    /// with whether it matches every such path.
+    pub fn live<'a>(&'a self, dir: &'a DirState) -> impl Iterator<Item = (usize, bool)> + 'a {
+        let floating = self.floating_names.values().flatten().chain(&self.floating_glob_indices);
+        dir.positions.iter()
+            .map(|&(i, n)| (i, self.covers(i, n)))
+            .chain(floating.map(|&i| (i, self.covers(i, 0))))
+            .chain(self.opaque.iter().map(|&(i, _)| (i, false)))
+    }
+
+    /// Returns `true` if pattern `i`, having matched up to component `n`,
+    /// matches whatever follows: if the rest is `**` or `**/*`.
+    fn covers(&self, i: usize, n: usize) -> bool {
+        match &self.programs[i][n..] {
+            [Component::Recursive] => true,
+            [Component::Recursive, Component::Glob(glob)] => glob.glob().glob() == "*",
+            _ => false,
+        }
+    }
+
+    /// Records that pattern `i` has matched up to component `n`: the path is
+    /// accepted if that's all of them, and otherwise `n` and, if it's a `**`
+    /// that may match nothing, what follows it are live.
+    fn enter(&self, i: usize, mut n: usize, into: &mut Vec<(usize, usize)>, accepted: &mut Vec<usize>) {
+        let program = &self.programs[i];
+        loop {
+            if n == program.len() {
+                accepted.push(i);
+                return;
+            }
+
+            into.push((i, n));
+            match program[n] {
+                Component::Recursive if n + 1 < program.len() => n += 1,
+                _ => return,
+            }
+        }
+    }
+
+    /// Descends from `dir` into its entry `name`, returning the entry's state.
+    /// The sorted indices of the patterns matching the entry are written into
+    /// `scratch.matches`, and the rest of `scratch` is used as scratch space,
+    /// so that descending only allocates the new state.
+    pub fn step(&self, dir: &DirState, name: &[u8], scratch: &mut Scratch) -> DirState {
+        let Scratch { matches: accepted, globs, path: buf } = scratch;
+        let mut positions = vec![];
+        accepted.clear();
+        for &(i, n) in &dir.positions {
+            let program = &self.programs[i];
+            match &program[n] {
+                Component::Recursive => {
+                    // A trailing `**` matches one or more components.
+                    self.enter(i, n, &mut positions, accepted);
+                    if n + 1 == program.len() {
+                        accepted.push(i);
+                    }
+                }
+                component if component.is_match(name) => self.enter(i, n + 1, &mut positions, accepted),
+                _ => {}
+            }
+        }
+
+        for &i in self.floating_names.get(name).into_iter().flatten() {
+            self.enter(i, 2, &mut positions, accepted);
+        }
+
+        if !self.floating_glob_indices.is_empty() {
+            let candidate = globset::Candidate::from_bytes(name);
+            self.floating_globs.matches_candidate_into(&candidate, globs);
+            for &j in globs.iter() {
+                self.enter(self.floating_glob_indices[j], 2, &mut positions, accepted);
+            }
+        }
+
+        let path = (!self.opaque.is_empty()).then(|| {
+            let node = Arc::new(PathNode { parent: dir.path.clone(), name: name.into() });
+            node.write(buf);
+            let candidate = globset::Candidate::from_bytes(buf);
+            let matching = self.opaque.iter().filter(|(_, glob)| glob.is_match_candidate(&candidate));
+            accepted.extend(matching.map(|(i, _)| *i));
+            node
+        });
+
+        positions.sort_unstable();
+        positions.dedup();
+        accepted.sort_unstable();
+        accepted.dedup();
+
+        DirState {
+            exhausted: self.is_exhausted(&positions),
+            matched: false,
+            positions,
+            path,
+        }
+    }
+}
+
+/// The components of `path`, as [`Automaton::step()`] expects them.
+pub(crate) fn components(path: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let path = (!path.is_empty()).then(|| path.strip_suffix(b"/").unwrap_or(path));
+    path.into_iter().flat_map(|path| path.split(|&b| b == b'/'))
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::format::canonical;
+use crate::memo::Exclusions;
+use crate::{walk, Error, Match, Matcher};
+
+/// The paths whose status changes between two [`Matcher`]s, grouped by the
+/// pattern responsible for the change.
+#[derive(Debug)]
+pub struct Diff<'a> {
+    /// Paths matched by the new matcher but not the old one.
+    pub ignored: Vec<Group<'a>>,
+    /// Paths matched by the old matcher but not the new one.
+    pub included: Vec<Group<'a>>,
+}
+
+/// Paths whose status changed because of the same pattern.
+#[derive(Debug)]
+pub struct Group<'a> {
+    /// The responsible pattern. For newly matched paths, this is the new
+    /// pattern that matches them, or a directory above them. For newly
+    /// unmatched paths, this is the new exception that excludes them or, if
+    /// there is none, the old pattern that no longer applies.
+    pub cause: Match<'a>,
+    /// The affected paths and whether each is a directory.
+    pub paths: Vec<(PathBuf, bool)>,
+}
+
+fn add<'a>(groups: &mut Vec<Group<'a>>, cause: Match<'a>, path: PathBuf, is_dir: bool) {
+    let same = |group: &&mut Group<'a>| {
+        std::ptr::eq(group.cause.pattern(), cause.pattern())
+            && matches!(group.cause, Match::Exception(_)) == matches!(cause, Match::Exception(_))
+    };
+
+    match groups.iter_mut().find(same) {
+        Some(group) => group.paths.push((path, is_dir)),
+        None => groups.push(Group { cause, paths: vec![(path, is_dir)] }),
+    }
+}
+
+impl<'a> Diff<'a> {
+    /// Compares `old` and `new` on every path in `paths`. Like git, each
+    /// matcher excludes every path below a directory it excludes, so the
+    /// pattern responsible for such a path is the one excluding the directory.
+    pub fn new<I, P>(old: &'a Matcher, new: &'a Matcher, paths: I) -> Diff<'a>
+        where I: IntoIterator<Item = (P, bool)>, P: AsRef<Path>
+    {
+        Diff::below(old, new, paths, Path::new(""))
+    }
+
+    /// Like [`Diff::new()`], for paths below `top`.
+    fn below<I, P>(old: &'a Matcher, new: &'a Matcher, paths: I, top: &Path) -> Diff<'a>
+        where I: IntoIterator<Item = (P, bool)>, P: AsRef<Path>
+    {
+        let (mut old, mut new) = (Exclusions::new(old, top), Exclusions::new(new, top));
+        let mut diff = Diff { ignored: vec![], included: vec![] };
+        for (path, is_dir) in paths {
+            let path = path.as_ref();
+            let (before, after) = (old.find_match(path, is_dir), new.find_match(path, is_dir));
+            match (before, after) {
+                (None | Some(Match::Exception(_)), Some(cause @ Match::Pattern(_))) => {
+                    add(&mut diff.ignored, cause, path.into(), is_dir);
+                }
+                (Some(Match::Pattern(_)), Some(cause @ Match::Exception(_)))
+                    | (Some(cause @ Match::Pattern(_)), None) => {
+                    add(&mut diff.included, cause, path.into(), is_dir);
+                }
+                _ => {}
+            }
+        }
+
+        diff
+    }
+
+    /// Compares `old` and `new` on every entry in the directory tree rooted
+    /// at `dir`. Paths are `dir` joined with the relative path of the entry.
+    pub fn tree<P: AsRef<Path>>(old: &'a Matcher, new: &'a Matcher, dir: P) -> Result<Self, Error> {
+        let dir = dir.as_ref();
+        Ok(Diff::below(old, new, walk::walk(dir)?, dir))
+    }
+
+    /// Returns `true` if no path changed status.
+    pub fn is_empty(&self) -> bool {
+        self.ignored.is_empty() && self.included.is_empty()
+    }
+}
+
+impl fmt::Display for Diff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_group(f: &mut fmt::Formatter<'_>, sign: char, group: &Group<'_>) -> fmt::Result {
+            let pattern = canonical(group.cause.pattern());
+            match (sign, group.cause) {
+                ('-', Match::Pattern(_)) => writeln!(f, "{} no longer matched by `{}`:", sign, pattern)?,
+                ('-', Match::Exception(_)) => writeln!(f, "{} excluded by `{}`:", sign, pattern)?,
+                _ => writeln!(f, "{} matched by `{}`:", sign, pattern)?,
+            }
+
+            for (path, is_dir) in &group.paths {
+                let slash = if *is_dir { "/" } else { "" };
+                writeln!(f, "    {}{}", path.display(), slash)?;
+            }
+
+            Ok(())
+        }
+
+        for group in &self.ignored {
+            write_group(f, '+', group)?;
+        }
+
+        for group in &self.included {
+            write_group(f, '-', group)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,344 @@
+use std::fmt;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use crate::pattern::intern_roots;
+use crate::{Error, Limits, Matcher, Pattern, PatternSet};
+
+/// A format-preserving `.gitignore` document.
+///
+/// Every line remembers its exact source text and line terminator, so a
+/// document that is parsed and written back without edits is reproduced
+/// byte-for-byte. Edited documents only differ in the edited lines.
+#[derive(Debug, Clone)]
+pub struct Document {
+    root: PathBuf,
+    lines: Vec<Line>,
+    newline: &'static str,
+    /// The limits enforced as lines are parsed.
+    limits: Limits,
+    /// The number of lines that are patterns.
+    pattern_count: usize,
+}
+
+/// A single line in a [`Document`].
+#[derive(Debug, Clone)]
+pub struct Line {
+    raw: String,
+    ending: &'static str,
+    kind: LineKind,
+}
+
+/// What a [`Line`] in a [`Document`] contains.
+#[derive(Debug, Clone)]
+pub enum LineKind {
+    /// An empty line or one containing only spaces. Like git, a line of
+    /// tabs is a pattern.
+    Blank,
+    /// A line starting with `#`.
+    Comment,
+    /// A line containing a pattern.
+    Pattern(Pattern),
+}
+
+/// Returns `line` without trailing spaces, unless they're escaped with `\`.
+pub(crate) fn trim_trailing_spaces(line: &str) -> &str {
+    let bytes = line.as_bytes();
+    let mut end = bytes.len();
+    while end > 0 && bytes[end - 1] == b' ' {
+        if end >= 2 && bytes[end - 2] == b'\\' {
+            break;
+        }
+
+        end -= 1;
+    }
+
+    &line[..end]
+}
+
+impl Line {
+    fn parse(raw: &str, ending: &'static str, root: &Path, limits: &Limits) -> Result<Line, Error> {
+        let trimmed = trim_trailing_spaces(raw);
+        let kind = if trimmed.is_empty() {
+            LineKind::Blank
+        } else if trimmed.starts_with('#') {
+            LineKind::Comment
+        } else {
+            limits.check_text(trimmed)?;
+            let pattern = Pattern::new(trimmed, root)?;
+            limits.check_contained(&pattern, trimmed)?;
+
+            LineKind::Pattern(pattern)
+        };
+
+        Ok(Line { raw: raw.into(), ending, kind })
+    }
+
+    /// The text of the line, without the line terminator.
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    /// The text of the line with insignificant trailing whitespace removed.
+    pub fn trimmed(&self) -> &str {
+        trim_trailing_spaces(&self.raw)
+    }
+
+    pub fn kind(&self) -> &LineKind {
+        &self.kind
+    }
+
+    pub fn is_blank(&self) -> bool {
+        matches!(self.kind, LineKind::Blank)
+    }
+
+    pub fn is_comment(&self) -> bool {
+        matches!(self.kind, LineKind::Comment)
+    }
+
+    /// The pattern on this line, if there is one.
+    pub fn pattern(&self) -> Option<&Pattern> {
+        match &self.kind {
+            LineKind::Pattern(pattern) => Some(pattern),
+            _ => None,
+        }
+    }
+
+    fn pattern_mut(&mut self) -> Option<&mut Pattern> {
+        match &mut self.kind {
+            LineKind::Pattern(pattern) => Some(pattern),
+            _ => None,
+        }
+    }
+}
+
+impl Document {
+    /// Parses `text` as the contents of a `.gitignore` whose patterns are
+    /// relative to `root`.
+    pub fn parse<P: AsRef<Path>>(text: &str, root: P) -> Result<Document, Error> {
+        Document::parse_limited(text, root, Limits::new())
+    }
+
+    /// Like [`Document::parse()`], but for untrusted ignore files: fails with
+    /// [`Error::EscapesRoot`] if any pattern, now or inserted later, escapes
+    /// `root` and so could match paths outside of it.
+    pub fn parse_sandboxed<P: AsRef<Path>>(text: &str, root: P) -> Result<Document, Error> {
+        Document::parse_limited(text, root, Limits::new().sandboxed(true))
+    }
+
+    /// Like [`Document::parse()`], but fails with [`Error::LimitExceeded`] as
+    /// soon as the document exceeds `limits`, before parsing any further.
+    /// The limits also apply to lines inserted later, and to the pattern sets
+    /// and matchers made from the document.
+    pub fn parse_limited<P: AsRef<Path>>(text: &str, root: P, limits: Limits) -> Result<Document, Error> {
+        let root = root.as_ref();
+        let newline = match text.find('\n') {
+            Some(i) if text[..i].ends_with('\r') => "\r\n",
+            _ => "\n",
+        };
+
+        let (mut lines, mut pattern_count) = (vec![], 0);
+        let mut rest = text;
+        while !rest.is_empty() {
+            let (raw, ending, next) = match rest.find('\n') {
+                Some(i) if rest[..i].ends_with('\r') => (&rest[..i - 1], "\r\n", &rest[i + 1..]),
+                Some(i) => (&rest[..i], "\n", &rest[i + 1..]),
+                None => (rest, "", ""),
+            };
+
+            let line = Line::parse(raw, ending, root, &limits)?;
+            if line.pattern().is_some() {
+                pattern_count += 1;
+                limits.check_count(pattern_count)?;
+            }
+
+            lines.push(line);
+            rest = next;
+        }
+
+        intern_roots(lines.iter_mut().filter_map(Line::pattern_mut));
+        Ok(Document { root: root.into(), lines, newline, limits, pattern_count })
+    }
+
+    /// The root that the patterns in this document are relative to.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Whether this document rejects patterns that escape its root. See
+    /// [`Document::parse_sandboxed()`].
+    pub fn is_sandboxed(&self) -> bool {
+        self.limits.is_sandboxed()
+    }
+
+    /// The limits this document was parsed with.
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// The line terminator used for new lines: the terminator of the first
+    /// line in the source text, or `\n` if there was none.
+    pub(crate) fn newline(&self) -> &'static str {
+        self.newline
+    }
+
+    /// Parses `text` as a single line and inserts it at `index`, shifting
+    /// all lines after it down.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, text: &str) -> Result<&mut Self, Error> {
+        assert!(index <= self.lines.len(), "insertion index out of bounds");
+        let text = text.trim_end_matches(['\r', '\n']);
+        let mut line = Line::parse(text, self.newline, &self.root, &self.limits)?;
+        if let Some(pattern) = line.pattern_mut() {
+            self.limits.check_count(self.pattern_count + 1)?;
+            if let Some(shared) = self.patterns().find(|p| p.root == pattern.root) {
+                pattern.root = shared.root.clone();
+            }
+
+            self.pattern_count += 1;
+        }
+
+        if index == self.lines.len() {
+            match self.lines.last_mut() {
+                Some(last) if last.ending.is_empty() => {
+                    last.ending = self.newline;
+                    line.ending = "";
+                }
+                _ => {}
+            }
+        }
+
+        self.lines.insert(index, line);
+        Ok(self)
+    }
+
+    /// Parses `text` as a single line and appends it to the document.
+    pub fn push(&mut self, text: &str) -> Result<&mut Self, Error> {
+        self.insert(self.lines.len(), text)
+    }
+
+    /// Removes and returns the line at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Line {
+        let line = self.lines.remove(index);
+        if line.pattern().is_some() {
+            self.pattern_count -= 1;
+        }
+
+        if index == self.lines.len() && line.ending.is_empty() {
+            if let Some(last) = self.lines.last_mut() {
+                last.ending = "";
+            }
+        }
+
+        line
+    }
+
+    /// Moves the line at `from` so that it ends up at index `to`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either index is out of bounds.
+    pub fn move_line(&mut self, from: usize, to: usize) -> &mut Self {
+        assert!(to < self.lines.len(), "destination index out of bounds");
+        let missing_ending = self.lines.last().is_some_and(|l| l.ending.is_empty());
+        if missing_ending {
+            let last = self.lines.len() - 1;
+            self.lines[last].ending = self.newline;
+        }
+
+        let line = self.lines.remove(from);
+        self.lines.insert(to, line);
+        if missing_ending {
+            let last = self.lines.len() - 1;
+            self.lines[last].ending = "";
+        }
+
+        self
+    }
+
+    /// Returns the index of the first pattern line whose text is `text`,
+    /// ignoring insignificant trailing whitespace.
+    pub fn position(&self, text: &str) -> Option<usize> {
+        let text = trim_trailing_spaces(text);
+        self.lines.iter().position(|line| line.pattern().is_some() && line.trimmed() == text)
+    }
+
+    /// Removes every pattern line that is repeated later in the document,
+    /// returning the number of lines removed.
+    ///
+    /// Because the last matching pattern wins, it is always the _earlier_
+    /// copies of a pattern that are redundant; the last copy is kept.
+    pub fn dedupe(&mut self) -> usize {
+        let mut seen = std::collections::HashSet::new();
+        let mut keep = vec![true; self.lines.len()];
+        for (i, line) in self.lines.iter().enumerate().rev() {
+            if let Some(pattern) = line.pattern() {
+                keep[i] = seen.insert(pattern.to_string());
+            }
+        }
+
+        let removed = keep.iter().filter(|k| !**k).count();
+        for i in (0..self.lines.len()).rev() {
+            if !keep[i] {
+                self.remove(i);
+            }
+        }
+
+        removed
+    }
+
+    /// Iterates over the patterns in the document, in order.
+    pub fn patterns(&self) -> impl Iterator<Item = &Pattern> {
+        self.lines.iter().filter_map(|line| line.pattern())
+    }
+
+    pub fn to_pattern_set(&self) -> PatternSet {
+        let mut set: PatternSet = self.patterns().cloned().collect();
+        set.limit(self.limits);
+        set
+    }
+
+    pub fn to_matcher(&self) -> Result<Matcher, Error> {
+        self.to_pattern_set().into_matcher()
+    }
+}
+
+impl Deref for Document {
+    type Target = [Line];
+
+    fn deref(&self) -> &Self::Target {
+        &self.lines
+    }
+}
+
+impl std::str::FromStr for Document {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Document::parse(s, "")
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)?;
+        f.write_str(self.ending)
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            line.fmt(f)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,499 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+use crate::pattern::{alternatives, is_literal, Shape};
+use crate::{pathext, Error, Pattern};
+
+/// A path prepared for matching against a [`Matcher`](crate::Matcher).
+///
+/// Preparing a candidate once and reusing it for several queries avoids
+/// repeatedly normalizing the path.
+#[derive(Debug, Clone)]
+pub struct Candidate<'a> {
+    pub(crate) path: Cow<'a, [u8]>,
+    pub(crate) glob: globset::Candidate<'a>,
+}
+
+impl<'a> Candidate<'a> {
+    /// Prepares `path`. On Unix, the path's bytes are used as they are, so
+    /// matching is exact even if the path isn't valid UTF-8. On other
+    /// platforms, the path is lossily converted to UTF-8.
+    pub fn new<P: AsRef<Path> + ?Sized>(path: &'a P) -> Candidate<'a> {
+        let path = path.as_ref();
+        Candidate {
+            path: pathext::normalized_bytes(path),
+            glob: globset::Candidate::new(path),
+        }
+    }
+
+    /// Prepares the path made of `bytes`, with components separated by `/`.
+    /// The bytes are never converted, so matching is byte-exact on every
+    /// platform, whether or not the bytes are valid UTF-8.
+    pub fn from_bytes(bytes: &'a [u8]) -> Candidate<'a> {
+        Candidate {
+            path: Cow::Borrowed(bytes),
+            glob: globset::Candidate::from_bytes(bytes),
+        }
+    }
+
+    /// The part of the path after the last `/`.
+    fn name(&self) -> &[u8] {
+        match self.path.iter().rposition(|&b| b == b'/') {
+            Some(i) => &self.path[i + 1..],
+            None => &self.path,
+        }
+    }
+}
+
+/// A bucketed pattern: the pattern at `index` matches paths under `prefix`
+/// with names ending in `suffix`, if there is one.
+#[derive(Debug, Clone)]
+struct Entry {
+    index: usize,
+    prefix: Box<[u8]>,
+    suffix: Box<[u8]>,
+}
+
+/// Up to `CHUNK_SIZE` globs compiled into one `GlobSet`.
+#[derive(Debug, Clone)]
+struct Chunk {
+    set: GlobSet,
+    globs: Vec<Glob>,
+    /// Index into `globs` -> index of the pattern.
+    indices: Vec<usize>,
+}
+
+/// The maximum number of globs in a chunk. Editing a pattern recompiles at
+/// most one chunk, so this bounds the globs compiled for an edit, though not
+/// the bookkeeping, which is linear in the number of patterns.
+const CHUNK_SIZE: usize = 128;
+
+/// A chunk recompiled for an edit: `chunk`'s globs will be `globs`.
+#[derive(Debug)]
+pub(crate) struct Staged {
+    chunk: usize,
+    set: GlobSet,
+    globs: Vec<Glob>,
+}
+
+/// A compiled set of patterns.
+///
+/// Most patterns in the wild are exact names (`node_modules`), extensions
+/// (`*.pyc`) or anchored literals (`/target`). Those, and alternations of
+/// them like `*.{o,obj}`, are bucketed into hash maps keyed by name,
+/// extension and full path, respectively. Only the
+/// remaining patterns are compiled, in chunks, into `GlobSet`s. Matching
+/// produces exactly the same results as matching every glob.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Engine {
+    /// Full path -> indices of patterns matching exactly that path.
+    literals: HashMap<Box<[u8]>, Vec<usize>>,
+    /// Name -> patterns matching paths with that name.
+    names: HashMap<Box<[u8]>, Vec<Entry>>,
+    /// Extension -> patterns matching paths with names ending in a suffix
+    /// that itself ends in the extension.
+    extensions: HashMap<Box<[u8]>, Vec<Entry>>,
+    /// The remaining patterns.
+    chunks: Vec<Chunk>,
+}
+
+impl Engine {
+    /// Compiles `patterns`, each identified by its index in the iterator.
+    pub fn new<'p, I>(patterns: I) -> Result<Engine, Error>
+        where I: IntoIterator<Item = (usize, &'p Pattern)>
+    {
+        let mut engine = Engine::default();
+        let mut complex = vec![];
+        for (i, pattern) in patterns {
+            if !engine.bucket(i, pattern) {
+                complex.push((i, pattern.glob.clone()));
+            }
+        }
+
+        for chunk in complex.chunks(CHUNK_SIZE) {
+            let (indices, globs): (Vec<_>, Vec<_>) = chunk.iter().cloned().unzip();
+            let set = compile(&globs)?;
+            engine.chunks.push(Chunk { set, globs, indices });
+        }
+
+        Ok(engine)
+    }
+
+    /// Adds the pattern at index `i` to its bucket, if it has one. Returns
+    /// `false` if it's complex and doesn't.
+    fn bucket(&mut self, i: usize, pattern: &Pattern) -> bool {
+        let Some(globs) = buckets(pattern) else {
+            return false;
+        };
+
+        for glob in &globs {
+            self.bucket_glob(i, glob);
+        }
+
+        true
+    }
+
+    /// Adds `glob`, one of the bucketed globs of the pattern at index `i`, to
+    /// its bucket.
+    fn bucket_glob(&mut self, i: usize, glob: &str) {
+        match Shape::of(glob) {
+            Shape::Literal(path) => {
+                self.literals.entry(path.as_bytes().into()).or_default().push(i);
+            }
+            Shape::Basename { prefix, name } => {
+                let entry = self.names.entry(name.as_bytes().into()).or_default();
+                entry.push(Entry { index: i, prefix: prefix.as_bytes().into(), suffix: [].into() });
+            }
+            Shape::Suffix { prefix, suffix } => {
+                let (_, ext) = suffix.rsplit_once('.').unwrap();
+                let entry = self.extensions.entry(ext.as_bytes().into()).or_default();
+                entry.push(Entry {
+                    index: i,
+                    prefix: prefix.as_bytes().into(),
+                    suffix: suffix.as_bytes().into(),
+                });
+            }
+            _ => unreachable!("only bucketed patterns are bucketed"),
+        }
+    }
+
+    /// Removes the pattern at index `i` from its bucket, if it has one.
+    fn unbucket(&mut self, i: usize, pattern: &Pattern) {
+        fn remove<T>(map: &mut HashMap<Box<[u8]>, Vec<T>>, key: &[u8], f: impl Fn(&T) -> bool) {
+            if let Some(values) = map.get_mut(key) {
+                values.retain(|v| !f(v));
+                if values.is_empty() {
+                    map.remove(key);
+                }
+            }
+        }
+
+        for glob in buckets(pattern).into_iter().flatten() {
+            match Shape::of(&glob) {
+                Shape::Literal(path) => remove(&mut self.literals, path.as_bytes(), |&j| j == i),
+                Shape::Basename { name, .. } => remove(&mut self.names, name.as_bytes(), |e| e.index == i),
+                Shape::Suffix { suffix, .. } if suffix.contains('.') => {
+                    let (_, ext) = suffix.rsplit_once('.').unwrap();
+                    remove(&mut self.extensions, ext.as_bytes(), |e| e.index == i)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Finds the chunk and position within it of the complex pattern at `i`.
+    fn locate(&self, i: usize) -> Option<(usize, usize)> {
+        self.chunks.iter().enumerate().find_map(|(c, chunk)| {
+            chunk.indices.iter().position(|&j| j == i).map(|k| (c, k))
+        })
+    }
+
+    /// Recompiles the chunk affected by removing the pattern at index `old`,
+    /// adding `new`, or both, without changing the engine. Returns `None` if
+    /// no chunk is affected.
+    ///
+    /// `new` replaces `old` in its chunk if both are complex, and is appended
+    /// to the last chunk with room otherwise.
+    pub fn stage(&self, old: Option<usize>, new: Option<&Pattern>) -> Result<Option<Staged>, Error> {
+        let removed = old.and_then(|i| self.locate(i));
+        let added = new.filter(|p| !bucketed(p)).map(|p| p.glob.clone());
+        let (chunk, mut globs) = match (removed, &added) {
+            (None, None) => return Ok(None),
+            (Some((c, _)), _) => (c, self.chunks[c].globs.clone()),
+            (None, Some(_)) => match self.chunks.last() {
+                Some(last) if last.globs.len() < CHUNK_SIZE => (self.chunks.len() - 1, last.globs.clone()),
+                _ => (self.chunks.len(), vec![]),
+            },
+        };
+
+        if let Some((_, k)) = removed {
+            globs.remove(k);
+        }
+
+        globs.extend(added);
+        Ok(Some(Staged { chunk, set: compile(&globs)?, globs }))
+    }
+
+    /// Removes the pattern at index `i`. Its chunk, if any, must be staged.
+    pub fn remove(&mut self, i: usize, pattern: &Pattern) {
+        self.unbucket(i, pattern);
+        if let Some((c, k)) = self.locate(i) {
+            self.chunks[c].indices.remove(k);
+        }
+    }
+
+    /// Applies `staged`, the chunk staged for an edit, and adds `new`, the
+    /// pattern at its index, if there is one.
+    pub fn apply(&mut self, staged: Option<Staged>, new: Option<(usize, &Pattern)>) {
+        let chunk = staged.map(|Staged { chunk, set, globs }| {
+            if chunk == self.chunks.len() {
+                self.chunks.push(Chunk { set, globs, indices: vec![] });
+            } else {
+                self.chunks[chunk].set = set;
+                self.chunks[chunk].globs = globs;
+            }
+
+            chunk
+        });
+
+        if let Some((i, pattern)) = new {
+            if !self.bucket(i, pattern) {
+                self.chunks[chunk.expect("complex patterns are staged")].indices.push(i);
+            }
+        }
+
+        self.chunks.retain(|chunk| !chunk.globs.is_empty());
+    }
+
+    /// Maps the index of every pattern through `f`.
+    pub fn renumber(&mut self, f: impl Fn(usize) -> usize) {
+        let literals = self.literals.values_mut().flatten();
+        let chunks = self.chunks.iter_mut().flat_map(|chunk| &mut chunk.indices);
+        for i in literals.chain(chunks) {
+            *i = f(*i);
+        }
+
+        let entries = self.names.values_mut().chain(self.extensions.values_mut()).flatten();
+        for entry in entries {
+            entry.index = f(entry.index);
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.literals.is_empty() && self.names.is_empty() && self.extensions.is_empty() && self.chunks.is_empty()
+    }
+
+    /// Appends the indices of all patterns matching `candidate`, in no
+    /// particular order, to `into`. `globs` is used as scratch space.
+    fn extend_matches(&self, candidate: &Candidate<'_>, into: &mut Vec<usize>, globs: &mut Vec<usize>) {
+        for chunk in &self.chunks {
+            chunk.set.matches_candidate_into(&candidate.glob, globs);
+            into.extend(globs.iter().map(|&i| chunk.indices[i]));
+        }
+
+        let path = &*candidate.path;
+        if let Some(indices) = self.literals.get(path) {
+            into.extend_from_slice(indices);
+        }
+
+        // `{prefix}**/{name}` matches `{prefix}{name}` and `{prefix}.../{name}`.
+        let name = candidate.name();
+        let name_start = path.len() - name.len();
+        if let Some(entries) = self.names.get(name) {
+            let matching = entries.iter()
+                .filter(|e| name_start >= e.prefix.len() && path.starts_with(&e.prefix));
+
+            into.extend(matching.map(|e| e.index));
+        }
+
+        // `{prefix}**/*{suffix}` matches names ending in `suffix` under `prefix`.
+        if let Some(dot) = name.iter().rposition(|&b| b == b'.') {
+            if let Some(entries) = self.extensions.get(&name[dot + 1..]) {
+                let matching = entries.iter().filter(|e| {
+                    name_start >= e.prefix.len()
+                        && path.starts_with(&e.prefix)
+                        && name.ends_with(&e.suffix)
+                });
+
+                into.extend(matching.map(|e| e.index));
+            }
+        }
+    }
+}
+
+/// Reusable scratch space for matching paths against a
+/// [`Matcher`](crate::Matcher).
+#[derive(Debug, Default, Clone)]
+pub struct Scratch {
+    pub(crate) matches: Vec<usize>,
+    pub(crate) globs: Vec<usize>,
+    /// The path of an entry being descended into.
+    pub(crate) path: Vec<u8>,
+}
+
+impl Scratch {
+    pub const fn new() -> Scratch {
+        Scratch { matches: Vec::new(), globs: Vec::new(), path: Vec::new() }
+    }
+}
+
+/// A change to the patterns an [`Index`] was built from.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Edit<'a> {
+    /// A pattern was inserted at an index, moving those after it up.
+    Insert(usize, &'a Pattern),
+    /// The pattern at an index was removed, moving those after it down.
+    Remove(usize, &'a Pattern),
+    /// The pattern at an index was replaced by another.
+    Replace(usize, &'a Pattern, &'a Pattern),
+}
+
+/// Patterns compiled into one [`Engine`] per root.
+///
+/// A pattern with root `/a` can only match `/a` and paths under it, so a query
+/// only needs to consult the engines for the roots that are ancestors of the
+/// path, and not every pattern.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Index {
+    shards: HashMap<Box<[u8]>, Engine>,
+}
+
+impl Index {
+    /// Compiles `patterns`, each identified by its index in the slice.
+    pub fn new(patterns: &[Pattern]) -> Result<Index, Error> {
+        let mut roots: HashMap<&[u8], Vec<(usize, &Pattern)>> = HashMap::new();
+        for (i, pattern) in patterns.iter().enumerate() {
+            roots.entry(Index::key(pattern)).or_default().push((i, pattern));
+        }
+
+        let mut shards = HashMap::with_capacity(roots.len());
+        for (root, patterns) in roots {
+            shards.insert(root.into(), Engine::new(patterns)?);
+        }
+
+        Ok(Index { shards })
+    }
+
+    /// Applies `edit` to the patterns the index was built from, recompiling at
+    /// most one chunk in each affected shard. Locating the pattern and
+    /// renumbering the ones after it visits every entry. On error, nothing
+    /// changes.
+    pub fn edit(&mut self, edit: Edit<'_>) -> Result<(), Error> {
+        let (old, new) = match edit {
+            Edit::Insert(i, pattern) => (None, Some((i, pattern))),
+            Edit::Remove(i, pattern) => (Some((i, pattern)), None),
+            Edit::Replace(i, old, new) => (Some((i, old)), Some((i, new))),
+        };
+
+        let mut keys: Vec<&[u8]> = old.iter().chain(&new).map(|(_, p)| Index::key(p)).collect();
+        keys.dedup();
+
+        // Compile everything before changing anything.
+        let mut staged = Vec::with_capacity(keys.len());
+        for key in keys {
+            let old = old.filter(|(_, p)| Index::key(p) == key).map(|(i, _)| i);
+            let new = new.filter(|(_, p)| Index::key(p) == key).map(|(_, p)| p);
+            let stage = match self.shards.get(key) {
+                Some(engine) => engine.stage(old, new)?,
+                None => Engine::default().stage(old, new)?,
+            };
+
+            staged.push((key, stage));
+        }
+
+        if let Some((i, pattern)) = old {
+            if let Some(engine) = self.shards.get_mut(Index::key(pattern)) {
+                engine.remove(i, pattern);
+            }
+        }
+
+        match (old, new) {
+            (None, Some((i, _))) => self.renumber(|j| if j >= i { j + 1 } else { j }),
+            (Some((i, _)), None) => self.renumber(|j| if j > i { j - 1 } else { j }),
+            _ => {}
+        }
+
+        for (key, stage) in staged {
+            let new = new.filter(|(_, p)| Index::key(p) == key);
+            let engine = self.shards.entry(key.into()).or_default();
+            engine.apply(stage, new);
+            if engine.is_empty() {
+                self.shards.remove(key);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn renumber(&mut self, f: impl Fn(usize) -> usize) {
+        for engine in self.shards.values_mut() {
+            engine.renumber(&f);
+        }
+    }
+
+    /// The shard for `pattern`: its root, if the pattern can only match the
+    /// root and paths below it, and the empty root otherwise.
+    fn key(pattern: &Pattern) -> &[u8] {
+        let root = &*pattern.root;
+        let below = match pattern.glob.glob().strip_prefix(root) {
+            Some(rest) => rest.is_empty() || rest.starts_with('/') || root.ends_with('/'),
+            None => false,
+        };
+
+        match is_literal(root) && below {
+            true => root.as_bytes(),
+            false => b"",
+        }
+    }
+
+    /// Writes the indices of all patterns matching `candidate`, in ascending
+    /// order, into `scratch.matches`.
+    pub fn matches_into(&self, candidate: &Candidate<'_>, scratch: &mut Scratch) {
+        let Scratch { matches, globs, .. } = scratch;
+        matches.clear();
+
+        let mut visit = |root: &[u8]| {
+            if let Some(engine) = self.shards.get(root) {
+                engine.extend_matches(candidate, matches, globs);
+            }
+        };
+
+        // A root `r` is an ancestor of the path if the path is `r`, or starts
+        // with `r` followed by `/`, or `r` ends in `/` and the path starts
+        // with `r`.
+        visit(b"");
+        if self.shards.len() > 1 || !self.shards.contains_key(&b""[..]) {
+            let path = &*candidate.path;
+            for (i, _) in path.iter().enumerate().filter(|(_, &b)| b == b'/') {
+                if i > 0 {
+                    visit(&path[..i]);
+                }
+
+                visit(&path[..=i]);
+            }
+
+            if !path.is_empty() && !path.ends_with(b"/") {
+                visit(path);
+            }
+        }
+
+        matches.sort_unstable();
+        matches.dedup();
+    }
+}
+
+/// Returns `true` if `pattern` is bucketed rather than compiled.
+fn bucketed(pattern: &Pattern) -> bool {
+    buckets(pattern).is_some()
+}
+
+/// The globs `pattern` is bucketed as, or `None` if it's compiled: its own
+/// glob or, so that merging patterns into an alternation like `*.{o,obj}`
+/// doesn't send them to the glob engine, each of its alternatives.
+fn buckets(pattern: &Pattern) -> Option<Vec<Cow<'_, str>>> {
+    let glob = pattern.glob.glob();
+    let globs = match alternatives(glob) {
+        Some(globs) => globs.into_iter().map(Cow::Owned).collect(),
+        None => vec![Cow::Borrowed(glob)],
+    };
+
+    let bucketable = |glob: &Cow<'_, str>| match Shape::of(glob) {
+        Shape::Literal(_) | Shape::Basename { .. } => true,
+        Shape::Suffix { suffix, .. } => suffix.contains('.'),
+        _ => false,
+    };
+
+    globs.iter().all(bucketable).then_some(globs)
+}
+
+fn compile(globs: &[Glob]) -> Result<GlobSet, Error> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(glob.clone());
+    }
+
+    Ok(builder.build()?)
+}
@@ -1,8 +1,16 @@
 use std::fmt;
 
+use crate::Limit;
+
 #[derive(Debug)]
 pub enum Error {
-    Glob(globset::Error)
+    Glob(globset::Error),
+    Io(std::io::Error),
+    SemanticsChanged { path: String, is_dir: bool },
+    Inseparable { path: String },
+    OutsideBase { path: String, base: String },
+    EscapesRoot { pattern: String, root: String },
+    LimitExceeded { limit: Limit, max: usize },
 }
 
 impl From<globset::Error> for Error {
@@ -11,10 +19,33 @@ impl From<globset::Error> for Error {
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Error {
+        Error::Io(value)
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Error::Glob(e) => e.fmt(f),
+            Error::Io(e) => e.fmt(f),
+            Error::SemanticsChanged { path, is_dir } => {
+                let kind = if *is_dir { "directory" } else { "file" };
+                write!(f, "rewrite changes whether {} `{}` is ignored", kind, path)
+            }
+            Error::Inseparable { path } => {
+                write!(f, "`{}` can't be separated from the tracked paths", path)
+            }
+            Error::OutsideBase { path, base } => {
+                write!(f, "`{}` is outside of the base directory `{}`", path, base)
+            }
+            Error::EscapesRoot { pattern, root } => {
+                write!(f, "pattern `{}` escapes its root `{}`", pattern, root)
+            }
+            Error::LimitExceeded { limit, max } => {
+                write!(f, "exceeded the limit of {} {}", max, limit)
+            }
         }
     }
 }
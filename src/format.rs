@@ -0,0 +1,133 @@
+use crate::{corpus, Document, Error, LineKind, Pattern};
+
+/// A canonical formatter for `.gitignore` documents.
+///
+/// Formatting writes every pattern as the canonical line for its parsed
+/// [`Pattern`], without insignificant trailing whitespace or redundant `**/`
+/// prefixes, removes duplicate patterns and, optionally, sorts patterns
+/// within sections. Every formatted document is checked against the original
+/// over a generated corpus of paths; if they disagree on any path, formatting
+/// fails with [`Error::SemanticsChanged`].
+#[derive(Debug, Default, Clone)]
+pub struct Formatter {
+    sort: bool,
+}
+
+/// Returns `pattern` as the canonical line that parses back to it, with the
+/// same root: its glob relative to the root, without redundant `**/`
+/// prefixes, and with a leading `/` only where one is needed to anchor it.
+pub(crate) fn canonical(pattern: &Pattern) -> String {
+    let (glob, root) = (pattern.glob(), pattern.root());
+    let relative = match glob.strip_prefix(root) {
+        Some(rest) if rest.is_empty() || root.is_empty() || root.ends_with('/') => Some(rest),
+        Some(rest) => rest.strip_prefix('/'),
+        None => None,
+    };
+
+    // Patterns that aren't below their root have no line of their own.
+    let Some(mut body) = relative else {
+        return pattern.to_string();
+    };
+
+    while body.starts_with("**/**/") {
+        body = &body[3..];
+    }
+
+    // `**/{name}` is written `{name}`, unless `{name}` would be mistaken for
+    // an exception or a comment. Otherwise, a body without a `/`, or one
+    // starting like an exception or comment, needs a leading `/`.
+    let mut anchor = !body.contains('/') || body.starts_with(['!', '#']);
+    if let Some(rest) = body.strip_prefix("**/") {
+        if !rest.is_empty() && !rest.contains('/') && !rest.starts_with(['!', '#']) {
+            (body, anchor) = (rest, false);
+        }
+    }
+
+    let mut line = String::with_capacity(body.len() + 3);
+    if pattern.exception {
+        line.push('!');
+    }
+
+    if anchor {
+        line.push('/');
+    }
+
+    line.push_str(body);
+    if line.ends_with(' ') && !line.ends_with("\\ ") {
+        line.insert(line.len() - 1, '\\');
+    }
+
+    if pattern.dir_only && !line.ends_with('/') {
+        line.push('/');
+    }
+
+    line
+}
+
+impl Formatter {
+    pub fn new() -> Self {
+        Formatter::default()
+    }
+
+    /// Whether to sort patterns within each section. A section is a run of
+    /// patterns uninterrupted by comments or blank lines. Only patterns of
+    /// the same polarity are reordered relative to each other, so exceptions
+    /// stay where they are. Defaults to `false`.
+    pub fn sort(mut self, yes: bool) -> Self {
+        self.sort = yes;
+        self
+    }
+
+    /// Returns the canonical line for `pattern`, relative to its root: the
+    /// line that [`Formatter::format()`] writes for it.
+    pub fn format_pattern(&self, pattern: &Pattern) -> String {
+        canonical(pattern)
+    }
+
+    /// Formats `document`, returning the canonical document.
+    pub fn format(&self, document: &Document) -> Result<Document, Error> {
+        let mut text = String::new();
+        for line in document.iter() {
+            match line.kind() {
+                LineKind::Blank => {},
+                LineKind::Comment => text.push_str(line.as_str().trim_end()),
+                LineKind::Pattern(pattern) => text.push_str(&canonical(pattern)),
+            }
+
+            text.push_str(document.newline());
+        }
+
+        let mut canonical = Document::parse(&text, document.root())?;
+        canonical.dedupe();
+        if self.sort {
+            let mut lines: Vec<&str> = canonical.iter().map(|l| l.as_str()).collect();
+            let mut run_start = 0;
+            for (i, line) in canonical.iter().enumerate() {
+                let exception = line.pattern().map(|p| p.exception);
+                let previous = i.checked_sub(1).and_then(|j| canonical[j].pattern());
+                if exception.is_none() || exception != previous.map(|p| p.exception) {
+                    lines[run_start..i].sort();
+                    run_start = i + exception.is_none() as usize;
+                }
+            }
+
+            lines[run_start..].sort();
+            text = lines.iter().map(|l| format!("{}{}", l, document.newline())).collect();
+            canonical = Document::parse(&text, document.root())?;
+        }
+
+        let (old, new) = (document.to_matcher()?, canonical.to_matcher()?);
+        let corpus = corpus::sample_paths(document.patterns());
+        if let Some((path, is_dir)) = corpus::first_difference(&old, &new, &corpus) {
+            return Err(Error::SemanticsChanged { path: path.into(), is_dir });
+        }
+
+        Ok(canonical)
+    }
+
+    /// Parses `text` as a `.gitignore` and returns its canonical form.
+    pub fn format_str(&self, text: &str) -> Result<String, Error> {
+        let document: Document = text.parse()?;
+        Ok(self.format(&document)?.to_string())
+    }
+}
@@ -1,9 +1,45 @@
 mod pathext;
 mod pattern;
 mod matcher;
+mod based;
+mod engine;
+mod descend;
+mod document;
+mod limits;
+mod format;
+mod minimize;
+mod suggest;
+mod diff;
+mod coverage;
+mod cache;
+mod memo;
+mod walk;
+mod corpus;
 mod error;
 
 pub use pattern::Pattern;
-pub use matcher::{Matcher, PatternSet};
+pub use matcher::{Matcher, Match, PatternId, PatternSet};
+pub use based::{BasedMatcher, PathMode};
+pub use document::{Document, Line, LineKind};
+pub use limits::{Limit, Limits};
+pub use format::Formatter;
+pub use diff::{Diff, Group};
+pub use coverage::{Coverage, PatternCoverage};
+pub use cache::{Status, StatusCache};
+pub use memo::CachedMatcher;
 pub use error::Error;
 pub use pathext::PathExt;
+pub use engine::{Candidate, Scratch};
+pub use descend::DirState;
+
+// Everything that can be shared across threads is `Send` and `Sync`.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Pattern>();
+    assert_send_sync::<PatternSet>();
+    assert_send_sync::<Matcher>();
+    assert_send_sync::<CachedMatcher>();
+    assert_send_sync::<Document>();
+    assert_send_sync::<DirState>();
+    assert_send_sync::<Candidate<'_>>();
+};
@@ -0,0 +1,117 @@
+use std::fmt;
+
+use crate::{Error, Pattern};
+
+/// Restrictions on ignore files from untrusted sources, bounding the
+/// resources they can use and the paths they can match.
+///
+/// Limits are enforced as a [`Document`](crate::Document) is parsed with
+/// [`Document::parse_limited()`](crate::Document::parse_limited()), and when
+/// a [`PatternSet`](crate::PatternSet) is compiled or a
+/// [`Matcher`](crate::Matcher) edited. By default, nothing is limited.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct Limits {
+    patterns: Option<usize>,
+    pattern_len: Option<usize>,
+    compiled_size: Option<usize>,
+    sandboxed: bool,
+}
+
+/// A limit in [`Limits`] that was exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    /// The number of patterns.
+    Patterns,
+    /// The length of a pattern, in bytes.
+    PatternLength,
+    /// The total length of the regular expressions that the patterns compile
+    /// to, in bytes, which bounds the memory used to compile them.
+    CompiledSize,
+}
+
+impl Limits {
+    pub fn new() -> Self {
+        Limits::default()
+    }
+
+    /// The maximum number of patterns.
+    pub fn max_patterns(mut self, max: usize) -> Self {
+        self.patterns = Some(max);
+        self
+    }
+
+    /// The maximum length of a pattern's text, in bytes.
+    pub fn max_pattern_len(mut self, max: usize) -> Self {
+        self.pattern_len = Some(max);
+        self
+    }
+
+    /// The maximum total length of the regular expressions that the patterns
+    /// compile to, in bytes. Alternations and classes are counted in full,
+    /// so this catches patterns that are short but costly to compile.
+    pub fn max_compiled_size(mut self, max: usize) -> Self {
+        self.compiled_size = Some(max);
+        self
+    }
+
+    /// Whether patterns that escape their root are rejected, as by
+    /// [`Document::parse_sandboxed()`](crate::Document::parse_sandboxed()).
+    pub fn sandboxed(mut self, sandboxed: bool) -> Self {
+        self.sandboxed = sandboxed;
+        self
+    }
+
+    pub(crate) fn is_sandboxed(&self) -> bool {
+        self.sandboxed
+    }
+
+    /// Checks that `pattern`, with text `text`, doesn't escape its root if
+    /// sandboxed.
+    pub(crate) fn check_contained(&self, pattern: &Pattern, text: &str) -> Result<(), Error> {
+        match self.sandboxed && pattern.escapes_root() {
+            true => Err(Error::EscapesRoot { pattern: text.into(), root: pattern.root.to_string() }),
+            false => Ok(()),
+        }
+    }
+
+    /// Checks that `count` patterns are allowed.
+    pub(crate) fn check_count(&self, count: usize) -> Result<(), Error> {
+        check(Limit::Patterns, self.patterns, count)
+    }
+
+    /// Checks that the pattern with text `text` is allowed.
+    pub(crate) fn check_text(&self, text: &str) -> Result<(), Error> {
+        check(Limit::PatternLength, self.pattern_len, text.len())
+    }
+
+    /// Checks that `count` patterns whose compiled sizes add up to `size` are
+    /// allowed to be compiled together.
+    pub(crate) fn check_compiled(&self, count: usize, size: usize) -> Result<(), Error> {
+        self.check_count(count)?;
+        check(Limit::CompiledSize, self.compiled_size, size)
+    }
+}
+
+/// The size `pattern` adds to the patterns it's compiled with, as limited by
+/// [`Limits::max_compiled_size()`].
+pub(crate) fn compiled_size(pattern: &Pattern) -> usize {
+    pattern.glob.regex().len()
+}
+
+fn check(limit: Limit, max: Option<usize>, actual: usize) -> Result<(), Error> {
+    match max {
+        Some(max) if actual > max => Err(Error::LimitExceeded { limit, max }),
+        _ => Ok(()),
+    }
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Patterns => write!(f, "patterns"),
+            Limit::PatternLength => write!(f, "bytes in a pattern"),
+            Limit::CompiledSize => write!(f, "bytes of compiled patterns"),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::process::ExitCode;
+use std::path::PathBuf;
+
+use gitfilter::{Coverage, Diff, Document, Formatter, StatusCache};
+
+const USAGE: &str = "\
+usage: gitfilter <command> [options]
+
+commands:
+    fmt [--sort] [--check] <file>...    format .gitignore files in place
+    diff <old> <new> [<dir>]            show paths in <dir> whose status changes
+    coverage <file> [<dir>]             show how often each pattern matches in <dir>
+    status [--cache <file>] [<dir>]     show ignored paths in <dir>, caching in <file>
+";
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+fn fmt(args: &[String]) -> Result<ExitCode> {
+    let (mut formatter, mut check, mut files) = (Formatter::new(), false, vec![]);
+    for arg in args {
+        match arg.as_str() {
+            "--sort" => formatter = formatter.sort(true),
+            "--check" => check = true,
+            file => files.push(file),
+        }
+    }
+
+    let mut unformatted = false;
+    for file in files {
+        let text = std::fs::read_to_string(file)?;
+        let formatted = formatter.format_str(&text).map_err(|e| format!("{}: {}", file, e))?;
+        if formatted == text {
+            continue;
+        }
+
+        if check {
+            println!("{}", file);
+            unformatted = true;
+        } else {
+            std::fs::write(file, formatted)?;
+        }
+    }
+
+    Ok(if unformatted { ExitCode::FAILURE } else { ExitCode::SUCCESS })
+}
+
+fn diff(args: &[String]) -> Result<ExitCode> {
+    let (old, new, dir) = match args {
+        [old, new] => (old, new, "."),
+        [old, new, dir] => (old, new, dir.as_str()),
+        _ => return Err(USAGE.into()),
+    };
+
+    let old = Document::parse(&std::fs::read_to_string(old)?, dir)?.to_matcher()?;
+    let new = Document::parse(&std::fs::read_to_string(new)?, dir)?.to_matcher()?;
+    let diff = Diff::tree(&old, &new, dir)?;
+    print!("{}", diff);
+    Ok(if diff.is_empty() { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
+
+fn coverage(args: &[String]) -> Result<ExitCode> {
+    let (file, dir) = match args {
+        [file] => (file, "."),
+        [file, dir] => (file, dir.as_str()),
+        _ => return Err(USAGE.into()),
+    };
+
+    let matcher = Document::parse(&std::fs::read_to_string(file)?, dir)?.to_matcher()?;
+    let coverage = Coverage::tree(&matcher, dir)?;
+    println!("{:>8} {:>8}  pattern", "hits", "decisive");
+    print!("{}", coverage);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn status(args: &[String]) -> Result<ExitCode> {
+    let (cache_file, dir) = match args {
+        [] => (None, "."),
+        [dir] => (None, dir.as_str()),
+        [flag, file] if flag == "--cache" => (Some(file), "."),
+        [flag, file, dir] if flag == "--cache" => (Some(file), dir.as_str()),
+        _ => return Err(USAGE.into()),
+    };
+
+    let mut cache = match cache_file {
+        Some(file) => StatusCache::load(dir, file)?,
+        None => StatusCache::new(dir),
+    };
+
+    // Like `git status --ignored`, an ignored directory stands for its entries.
+    let mut ignored_dir: Option<PathBuf> = None;
+    for status in cache.status()?.into_iter().filter(|status| status.ignored) {
+        if ignored_dir.as_ref().is_some_and(|dir| status.path.starts_with(dir)) {
+            continue;
+        }
+
+        println!("{}{}", status.path.display(), if status.is_dir { "/" } else { "" });
+        if status.is_dir {
+            ignored_dir = Some(status.path);
+        }
+    }
+
+    if let Some(file) = cache_file {
+        cache.save(file)?;
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(|s| s.as_str()) {
+        Some("fmt") => fmt(&args[1..]),
+        Some("diff") => diff(&args[1..]),
+        Some("coverage") => coverage(&args[1..]),
+        Some("status") => status(&args[1..]),
+        _ => {
+            eprint!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    result.unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        ExitCode::FAILURE
+    })
+}
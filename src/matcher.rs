@@ -1,19 +1,59 @@
 use std::{fmt, ops::Deref};
+use std::cell::RefCell;
+use std::ffi::OsStr;
+use std::fs;
 use std::path::Path;
+use std::sync::{Arc, OnceLock};
 
-use globset::{GlobSet, GlobSetBuilder};
-
-use crate::{Pattern, Error};
+use crate::engine::{Candidate, Edit, Index, Scratch};
+use crate::descend::{self, Automaton, DirState};
+use crate::{limits, pathext, pattern, Error, Limits, Pattern};
 
 #[derive(Default, Debug)]
 pub struct PatternSet {
-    patterns: Vec<Pattern>,
+    pub(crate) patterns: Vec<Pattern>,
+    /// The ID of each pattern in `patterns`.
+    pub(crate) ids: Vec<PatternId>,
+    next_id: u64,
+    /// Whether patterns and paths are normalized to NFC before matching.
+    precompose: bool,
+    limits: Limits,
 }
 
-#[derive(Debug)]
+/// A compiled [`PatternSet`].
+///
+/// Cloning a matcher is cheap: clones share their patterns and compiled
+/// state, which is only copied when a clone is edited. A matcher is `Send`
+/// and `Sync`, so one can be shared by a thread pool.
+#[derive(Debug, Clone)]
 pub struct Matcher {
-    patterns: Vec<Pattern>,
-    matcher: GlobSet,
+    patterns: Arc<Vec<Pattern>>,
+    /// The ID of each pattern in `patterns`.
+    ids: Arc<Vec<PatternId>>,
+    next_id: u64,
+    /// Whether patterns and paths are normalized to NFC before matching.
+    precompose: bool,
+    /// The limits enforced when patterns are added.
+    limits: Limits,
+    /// The sum of the patterns' compiled sizes, checked against `limits`.
+    compiled_size: usize,
+    index: Arc<Index>,
+    /// Compiled on first use by [`Matcher::descend()`], and shared by clones.
+    automaton: Arc<OnceLock<Automaton>>,
+}
+
+/// Identifies a pattern in a [`PatternSet`] or [`Matcher`] across edits that
+/// move it. IDs are preserved when a set is compiled into a matcher and back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PatternId(u64);
+
+/// The pattern that decided a [`Matcher`] query.
+#[derive(Debug, Clone, Copy)]
+pub enum Match<'a> {
+    /// A pattern matched: the path is matched.
+    Pattern(&'a Pattern),
+    /// An exception (`!`) matched: the path is not matched.
+    Exception(&'a Pattern),
 }
 
 impl PatternSet {
@@ -22,28 +62,122 @@ impl PatternSet {
     }
 
     pub fn add(&mut self, pattern: Pattern) -> &mut Self {
-        self.patterns.push(pattern);
+        self.push(pattern);
         self
     }
 
     pub fn extend<I: IntoIterator<Item = Pattern>>(&mut self, patterns: I) -> &mut Self {
-        self.patterns.extend(patterns);
+        for pattern in patterns {
+            self.push(pattern);
+        }
+
         self
     }
 
+    /// Adds `pattern` after all other patterns, returning its ID. Like every
+    /// edit to a set, this isn't checked against the set's limits until the
+    /// set is compiled.
+    pub fn push(&mut self, pattern: Pattern) -> PatternId {
+        self.insert_at(self.patterns.len(), pattern)
+    }
+
+    /// Inserts `pattern` at `index`, before the pattern currently there, and
+    /// returns its ID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert_at(&mut self, index: usize, pattern: Pattern) -> PatternId {
+        let id = PatternId(self.next_id);
+        self.next_id += 1;
+        self.patterns.insert(index, pattern);
+        self.ids.insert(index, id);
+        id
+    }
+
+    /// Removes the pattern with ID `id` and returns it, or `None` if there's
+    /// no such pattern.
+    pub fn remove(&mut self, id: PatternId) -> Option<Pattern> {
+        let index = self.position(id)?;
+        self.ids.remove(index);
+        Some(self.patterns.remove(index))
+    }
+
+    /// Retains only the patterns for which `f` returns `true`, in order.
+    pub fn retain<F: FnMut(&Pattern) -> bool>(&mut self, mut f: F) -> &mut Self {
+        let (ids, patterns) = std::mem::take(&mut self.ids).into_iter()
+            .zip(std::mem::take(&mut self.patterns))
+            .filter(|(_, pattern)| f(pattern))
+            .unzip();
+
+        (self.ids, self.patterns) = (ids, patterns);
+        self
+    }
+
+    /// Returns the ID of the pattern at `index`, if there is one.
+    pub fn id(&self, index: usize) -> Option<PatternId> {
+        self.ids.get(index).copied()
+    }
+
+    /// Returns the current index of the pattern with ID `id`, if there is one.
+    pub fn position(&self, id: PatternId) -> Option<usize> {
+        self.ids.iter().position(|&i| i == id)
+    }
+
+    /// Returns the pattern with ID `id`, if there is one.
+    pub fn get(&self, id: PatternId) -> Option<&Pattern> {
+        self.position(id).map(|i| &self.patterns[i])
+    }
+
+    #[allow(clippy::should_implement_trait)]
     pub fn into_iter(self) -> impl Iterator<Item = Pattern> {
         self.patterns.into_iter()
     }
 
+    /// Sets whether the matcher compiled from this set normalizes its
+    /// patterns, and every path it's queried with, to Unicode Normalization
+    /// Form C, like git's `core.precomposeUnicode`. This lets patterns match
+    /// paths however either was composed, such as an NFC `café` and an NFD
+    /// `cafe\u{301}` from a macOS file system.
+    #[cfg(feature = "unicode-normalization")]
+    pub fn precompose_unicode(&mut self, precompose: bool) -> &mut Self {
+        self.precompose = precompose;
+        self
+    }
+
+    /// Sets the limits enforced when this set is compiled, and when patterns
+    /// are then added to the matcher: either fails with
+    /// [`Error::LimitExceeded`], before compiling anything, if the patterns
+    /// would exceed them, or with [`Error::EscapesRoot`] if sandboxed and a
+    /// pattern escapes its root. The set itself may hold any patterns:
+    /// they're only checked once it's compiled.
+    pub fn limit(&mut self, limits: Limits) -> &mut Self {
+        self.limits = limits;
+        self
+    }
+
     pub fn into_matcher(self) -> Result<Matcher, Error> {
-        let mut globset = GlobSetBuilder::new();
-        for pattern in &self.patterns {
-            globset.add(pattern.glob.clone());
+        let mut patterns = self.patterns;
+        if self.precompose {
+            patterns = patterns.iter().map(precomposed).collect::<Result<_, _>>()?;
         }
 
+        for pattern in &patterns {
+            self.limits.check_contained(pattern, &pattern.to_string())?;
+        }
+
+        let compiled_size = patterns.iter().map(limits::compiled_size).sum();
+        self.limits.check_compiled(patterns.len(), compiled_size)?;
+        pattern::intern_roots(&mut patterns);
         Ok(Matcher {
-            patterns: self.patterns,
-            matcher: globset.build()?,
+            compiled_size,
+            index: Arc::new(Index::new(&patterns)?),
+            ids: Arc::new(self.ids),
+            next_id: self.next_id,
+            precompose: self.precompose,
+            limits: self.limits,
+            patterns: Arc::new(patterns),
+            automaton: Arc::default(),
         })
     }
 }
@@ -56,77 +190,405 @@ impl Deref for PatternSet {
     }
 }
 
-struct RawMatchResults<'a> {
-    patterns: &'a [Pattern],
-    matches: Vec<usize>,
-    pattern_i: usize,
-    match_i: usize,
+thread_local! {
+    /// Scratch space for the indices of the patterns matching a path, reused
+    /// across queries so that matching doesn't allocate.
+    static SCRATCH: RefCell<Scratch> = const { RefCell::new(Scratch::new()) };
 }
 
-struct RawMatchResult<'a> {
-    pattern: &'a Pattern,
-    matched: bool
-}
+impl Matcher {
+    /// Returns `pattern` as this matcher matches it: precomposed if the
+    /// matcher precomposes Unicode.
+    fn prepare(&self, pattern: Pattern) -> Result<Pattern, Error> {
+        match self.precompose {
+            true => precomposed(&pattern),
+            false => Ok(pattern),
+        }
+    }
 
-impl<'a> Iterator for RawMatchResults<'a> {
-    type Item = RawMatchResult<'a>;
+    /// Calls `f` with `candidate`, or with its precomposed form if this
+    /// matcher precomposes Unicode and the candidate isn't precomposed.
+    fn with_candidate<T>(&self, candidate: &Candidate<'_>, f: impl FnOnce(&Candidate<'_>) -> T) -> T {
+        #[cfg(feature = "unicode-normalization")]
+        if self.precompose {
+            let path = std::str::from_utf8(&candidate.path).ok();
+            if let Some(path) = path.and_then(pathext::precompose) {
+                return f(&Candidate::from_bytes(path.as_bytes()));
+            }
+        }
+
+        f(candidate)
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.pattern_i >= self.patterns.len() {
-            return None;
+    /// Like [`Matcher::with_candidate()`] but for the bytes of a path.
+    fn with_path<T>(&self, path: &[u8], f: impl FnOnce(&[u8]) -> T) -> T {
+        #[cfg(feature = "unicode-normalization")]
+        if self.precompose {
+            let string = std::str::from_utf8(path).ok();
+            if let Some(path) = string.and_then(pathext::precompose) {
+                return f(path.as_bytes());
+            }
         }
 
-        let matched = self.matches.get(self.match_i) == Some(&self.pattern_i);
-        if matched {
-            self.match_i += 1;
+        f(path)
+    }
+
+    /// Whether this matcher normalizes patterns and paths to NFC. See
+    /// [`PatternSet::precompose_unicode()`].
+    pub fn precomposes_unicode(&self) -> bool {
+        self.precompose
+    }
+
+    fn truly_matches(&self, index: usize, is_dir: bool) -> bool {
+        !self.patterns[index].dir_only || is_dir
+    }
+
+    /// Returns the index of the pattern among `matches`, the sorted indices of
+    /// the globs matching a path, that decides whether the path is matched.
+    ///
+    /// The last pattern that truly matches wins, so we scan from the end and
+    /// stop at the first pattern that truly matches. Only this scan stops
+    /// early: every pattern matching the path has already been found.
+    fn decide(&self, matches: &[usize], is_dir: bool) -> Option<usize> {
+        self.decide_with(matches, || is_dir)
+    }
+
+    /// Like [`Matcher::decide()`] but only calls `is_dir`, at most once, when
+    /// the scan reaches a pattern that only matches directories.
+    fn decide_with(&self, matches: &[usize], is_dir: impl FnOnce() -> bool) -> Option<usize> {
+        let (mut is_dir, mut known) = (Some(is_dir), None);
+        matches.iter().rev().copied().find(|&i| {
+            !self.patterns[i].dir_only
+                || *known.get_or_insert_with(|| is_dir.take().expect("called at most once")())
+        })
+    }
+
+    /// Iterates over the indices of the patterns that truly match `path`:
+    /// those that match it and, if they are `dir_only`, only if `is_dir`.
+    pub(crate) fn true_matches(&self, path: &Path, is_dir: bool) -> impl Iterator<Item = usize> + '_ {
+        let mut scratch = Scratch::new();
+        self.with_candidate(&Candidate::new(path), |c| self.index.matches_into(c, &mut scratch));
+        scratch.matches.into_iter()
+            .filter(move |&i| self.truly_matches(i, is_dir))
+    }
+
+    /// Iterates over every pattern that truly matches `path`, in order.
+    pub fn find_matches<P: AsRef<Path>>(&self, path: P, is_dir: bool) -> impl Iterator<Item = Match<'_>> {
+        self.true_matches(path.as_ref(), is_dir).map(|i| Match::new(&self.patterns[i]))
+    }
+
+    /// Like [`Matcher::find_matches()`] but for a prepared [`Candidate`], using
+    /// `scratch` to hold the matches instead of allocating.
+    pub fn find_matches_into<'a>(
+        &'a self,
+        candidate: &Candidate<'_>,
+        is_dir: bool,
+        scratch: &'a mut Scratch,
+    ) -> impl Iterator<Item = Match<'a>> + 'a {
+        self.with_candidate(candidate, |c| self.index.matches_into(c, scratch));
+        scratch.matches.iter()
+            .filter(move |&&i| self.truly_matches(i, is_dir))
+            .map(|&i| Match::new(&self.patterns[i]))
+    }
+
+    /// Returns the pattern that decides whether `path` is matched: the last
+    /// pattern that truly matches it, if any.
+    pub fn find_match<P: AsRef<Path>>(&self, path: P, is_dir: bool) -> Option<Match<'_>> {
+        let candidate = Candidate::new(path.as_ref());
+        with_scratch(|scratch| {
+            self.with_candidate(&candidate, |c| self.index.matches_into(c, scratch));
+            self.decide(&scratch.matches, is_dir).map(|i| Match::new(&self.patterns[i]))
+        })
+    }
+
+    /// Like [`Matcher::matches()`] but for a prepared [`Candidate`], using
+    /// `scratch` to hold intermediate results. Reusing a candidate and scratch
+    /// space across calls means matching never allocates.
+    pub fn matches_candidate_into(
+        &self,
+        candidate: &Candidate<'_>,
+        is_dir: bool,
+        scratch: &mut Scratch,
+    ) -> bool {
+        self.with_candidate(candidate, |c| self.index.matches_into(c, scratch));
+        self.decide(&scratch.matches, is_dir).is_some_and(|i| !self.patterns[i].exception)
+    }
+
+    /// Like [`Matcher::matches()`] but for a prepared [`Candidate`].
+    pub fn matches_candidate(&self, candidate: &Candidate<'_>, is_dir: bool) -> bool {
+        with_scratch(|scratch| self.matches_candidate_into(candidate, is_dir, scratch))
+    }
+
+    /// Returns `true` if `path` is matched: if the last pattern that truly
+    /// matches it is not an exception.
+    ///
+    /// This doesn't allocate on Unix; intermediate results are kept in
+    /// thread-local scratch space. On Unix, `path` may be any `OsStr`: its
+    /// bytes are matched exactly, even if they aren't valid UTF-8.
+    pub fn matches<P: AsRef<Path>>(&self, path: P, is_dir: bool) -> bool {
+        self.matches_candidate(&Candidate::new(path.as_ref()), is_dir)
+    }
+
+    /// Like [`Matcher::matches()`] but only calls `is_dir` to learn whether
+    /// `path` is a directory if that decides the result: if the last pattern
+    /// matching `path` only matches directories. `is_dir` is called at most
+    /// once, so it can be as costly as a `stat`.
+    pub fn matches_with<P, F>(&self, path: P, is_dir: F) -> bool
+        where P: AsRef<Path>, F: FnOnce() -> bool
+    {
+        let candidate = Candidate::new(path.as_ref());
+        with_scratch(|scratch| {
+            self.with_candidate(&candidate, |c| self.index.matches_into(c, scratch));
+            self.decide_with(&scratch.matches, is_dir).is_some_and(|i| !self.patterns[i].exception)
+        })
+    }
+
+    /// Like [`Matcher::matches_with()`], learning whether `path` is a
+    /// directory from the file system, if it needs to. Like git, symbolic
+    /// links aren't followed, and a path that can't be read isn't a
+    /// directory. Relative paths are relative to the current directory.
+    pub fn matches_fs<P: AsRef<Path>>(&self, path: P) -> bool {
+        let path = path.as_ref();
+        self.matches_with(path, || fs::symlink_metadata(path).is_ok_and(|m| m.is_dir()))
+    }
+
+    /// Like [`Matcher::matches()`] but for a path given as bytes, with
+    /// components separated by `/`. The bytes are matched exactly as they
+    /// are, even if they aren't valid UTF-8: two paths differing only in
+    /// invalid bytes are never confused.
+    pub fn matches_bytes(&self, path: &[u8], is_dir: bool) -> bool {
+        self.matches_candidate(&Candidate::from_bytes(path), is_dir)
+    }
+
+    /// Matches every `(path, is_dir)` in `paths`, returning the results in
+    /// input order. Candidates are prepared once, up front, and all queries
+    /// share the same scratch space.
+    pub fn matches_batch<I, P>(&self, paths: I) -> Vec<bool>
+        where I: IntoIterator<Item = (P, bool)>, P: AsRef<Path>
+    {
+        let paths: Vec<(P, bool)> = paths.into_iter().collect();
+        let candidates: Vec<(Candidate<'_>, bool)> = paths.iter()
+            .map(|(path, is_dir)| (Candidate::new(path.as_ref()), *is_dir))
+            .collect();
+
+        let mut scratch = Scratch::new();
+        candidates.iter()
+            .map(|(candidate, is_dir)| self.matches_candidate_into(candidate, *is_dir, &mut scratch))
+            .collect()
+    }
+
+    /// Like [`Matcher::matches_batch()`] but matches paths in parallel.
+    #[cfg(feature = "rayon")]
+    pub fn par_matches_batch<P>(&self, paths: &[(P, bool)]) -> Vec<bool>
+        where P: AsRef<Path> + Sync
+    {
+        use rayon::prelude::*;
+
+        paths.par_iter()
+            .map_init(Scratch::new, |scratch, (path, is_dir)| {
+                let candidate = Candidate::new(path.as_ref());
+                self.matches_candidate_into(&candidate, *is_dir, scratch)
+            })
+            .collect()
+    }
+
+    /// Converts this matcher back into the set of patterns it was compiled
+    /// from, with their IDs, for editing and recompiling.
+    pub fn into_pattern_set(self) -> PatternSet {
+        PatternSet {
+            patterns: Arc::unwrap_or_clone(self.patterns),
+            ids: Arc::unwrap_or_clone(self.ids),
+            next_id: self.next_id,
+            precompose: self.precompose,
+            limits: self.limits,
         }
+    }
+
+    /// Returns the ID of the pattern at `index`, if there is one.
+    pub fn id(&self, index: usize) -> Option<PatternId> {
+        self.ids.get(index).copied()
+    }
 
-        let result = RawMatchResult {
-            matched,
-            pattern: &self.patterns[self.pattern_i],
+    /// Returns the current index of the pattern with ID `id`, if there is one.
+    pub fn position(&self, id: PatternId) -> Option<usize> {
+        self.ids.iter().position(|&i| i == id)
+    }
+
+    /// Returns the pattern with ID `id`, if there is one.
+    pub fn get(&self, id: PatternId) -> Option<&Pattern> {
+        self.position(id).map(|i| &self.patterns[i])
+    }
+
+    /// Adds `pattern` after all other patterns, returning its ID.
+    pub fn push(&mut self, pattern: Pattern) -> Result<PatternId, Error> {
+        self.insert(self.patterns.len(), pattern)
+    }
+
+    /// Inserts `pattern` at `index`, before the pattern currently there, and
+    /// returns its ID.
+    ///
+    /// Only the part of the compiled matcher holding `pattern` is recompiled,
+    /// so no more than a bounded number of globs are compiled. The rest of
+    /// the edit is still linear in the number of patterns: the patterns
+    /// after `index` are renumbered, and the state used by
+    /// [`Matcher::descend()`] is rebuilt the next time it's used. On error,
+    /// the matcher is unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, pattern: Pattern) -> Result<PatternId, Error> {
+        assert!(index <= self.patterns.len(), "insertion index {} out of bounds", index);
+        let pattern = self.prepare(pattern)?;
+        self.limits.check_contained(&pattern, &pattern.to_string())?;
+        let compiled_size = self.compiled_size + limits::compiled_size(&pattern);
+        self.limits.check_compiled(self.patterns.len() + 1, compiled_size)?;
+        Arc::make_mut(&mut self.index).edit(Edit::Insert(index, &pattern))?;
+        self.compiled_size = compiled_size;
+
+        let id = PatternId(self.next_id);
+        self.next_id += 1;
+        Arc::make_mut(&mut self.patterns).insert(index, pattern);
+        Arc::make_mut(&mut self.ids).insert(index, id);
+        self.automaton = Arc::default();
+        Ok(id)
+    }
+
+    /// Removes the pattern with ID `id` and returns it, or `None` if there's
+    /// no such pattern. Like [`Matcher::insert()`], only part of the matcher is
+    /// recompiled, and on error, the matcher is unchanged.
+    pub fn remove(&mut self, id: PatternId) -> Result<Option<Pattern>, Error> {
+        let Some(index) = self.position(id) else {
+            return Ok(None);
         };
 
-        self.pattern_i += 1;
-        Some(result)
+        Arc::make_mut(&mut self.index).edit(Edit::Remove(index, &self.patterns[index]))?;
+        self.compiled_size -= limits::compiled_size(&self.patterns[index]);
+        Arc::make_mut(&mut self.ids).remove(index);
+        self.automaton = Arc::default();
+        Ok(Some(Arc::make_mut(&mut self.patterns).remove(index)))
+    }
+
+    /// Replaces the pattern with ID `id` by `pattern`, which takes its place
+    /// and ID, and returns the replaced pattern. Returns `None`, leaving the
+    /// matcher unchanged, if there's no such pattern. Like
+    /// [`Matcher::insert()`], only part of the matcher is recompiled, and on
+    /// error, the matcher is unchanged.
+    pub fn replace(&mut self, id: PatternId, pattern: Pattern) -> Result<Option<Pattern>, Error> {
+        let Some(index) = self.position(id) else {
+            return Ok(None);
+        };
+
+        let pattern = self.prepare(pattern)?;
+        self.limits.check_contained(&pattern, &pattern.to_string())?;
+        let old = &self.patterns[index];
+        let compiled_size = self.compiled_size - limits::compiled_size(old) + limits::compiled_size(&pattern);
+        self.limits.check_compiled(self.patterns.len(), compiled_size)?;
+        Arc::make_mut(&mut self.index).edit(Edit::Replace(index, old, &pattern))?;
+        self.compiled_size = compiled_size;
+        self.automaton = Arc::default();
+        Ok(Some(std::mem::replace(&mut Arc::make_mut(&mut self.patterns)[index], pattern)))
+    }
+
+    fn automaton(&self) -> &Automaton {
+        self.automaton.get_or_init(|| Automaton::new(&self.patterns))
+    }
+
+    /// Returns the state of the directory `dir`, from which
+    /// [`Matcher::descend()`] can match the entries below it.
+    pub fn dir_state<P: AsRef<Path>>(&self, dir: P) -> DirState {
+        let path = pathext::normalized_bytes(dir.as_ref());
+        self.with_path(&path, |path| {
+            descend::components(path)
+                .fold(self.automaton().start(), |state, name| self.descend_into(&state, name, true))
+        })
+    }
+
+    /// Returns the state of the entry `name` in the directory with state
+    /// `dir`. The entry is matched exactly as [`Matcher::matches()`] would
+    /// match `dir/name`, but only `name` is examined: what's known about
+    /// `dir` is carried over from its state.
+    ///
+    /// If [`DirState::is_exhausted()`] for a directory, no pattern can match
+    /// anything below it, and a walker need not descend any further.
+    pub fn descend<N: AsRef<OsStr>>(&self, dir: &DirState, name: N, is_dir: bool) -> DirState {
+        let name = pathext::normalized_bytes(Path::new(name.as_ref()));
+        self.with_path(&name, |name| self.descend_into(dir, name, is_dir))
+    }
+
+    /// Returns `true` if some path below the directory `dir` may not be
+    /// matched. If this returns `false`, every path below `dir` is matched,
+    /// and a walker can skip the subtree without missing an exception.
+    ///
+    /// This is decided from the structure of the patterns, without looking
+    /// at any path below `dir`, so it may return `true` even if every path
+    /// is in fact matched.
+    pub fn may_include_descendants<P: AsRef<Path>>(&self, dir: P) -> bool {
+        let state = self.dir_state(dir);
+        let live: Vec<(usize, bool)> = self.automaton().live(&state).collect();
+        let covering = live.iter()
+            .filter(|&&(i, covers)| covers && !self.patterns[i].exception && !self.patterns[i].dir_only)
+            .map(|&(i, _)| i)
+            .max();
+
+        // Only a later exception can exclude a path the covering pattern matches.
+        match covering {
+            Some(c) => live.iter().any(|&(i, _)| i > c && self.patterns[i].exception),
+            None => true,
+        }
+    }
+
+    /// Returns `true` if some path below the directory `dir` may be matched.
+    /// If this returns `false`, no path below `dir` is matched.
+    ///
+    /// Like [`Matcher::may_include_descendants()`], this is decided from the
+    /// structure of the patterns and may return `true` spuriously.
+    pub fn may_ignore_descendants<P: AsRef<Path>>(&self, dir: P) -> bool {
+        let state = self.dir_state(dir);
+        let mut live = self.automaton().live(&state);
+        live.any(|(i, _)| !self.patterns[i].exception)
+    }
+
+    fn descend_into(&self, dir: &DirState, name: &[u8], is_dir: bool) -> DirState {
+        with_scratch(|scratch| {
+            let mut state = self.automaton().step(dir, name, scratch);
+            state.matched = self.decide(&scratch.matches, is_dir).is_some_and(|i| !self.patterns[i].exception);
+            state
+        })
     }
 }
 
-impl Matcher {
-    fn raw_match_results(&self, path: &Path) -> RawMatchResults<'_> {
-        RawMatchResults {
-            patterns: &self.patterns,
-            matches: self.matcher.matches(path),
-            pattern_i: 0,
-            match_i: 0,
-        }
-    }
-
-    // TODO: Return something that allows identifying the pattern that matched,
-    // if any. Maybe a new method: `find_match() -> Option<Match>`:
-    // enum Match {
-    //     Pattern(&Pattern),
-    //     Exception(&Pattern),
-    // }
-    //
-    // We could even have a `find_matches()` that returns all rules that
-    // matched. `find_matches() -> Vec<Match>` or `impl Iterator<Item = MatcH>`.
-    pub fn matches<P: AsRef<Path>>(&self, path: P, is_dir: bool) -> bool {
-        let path = path.as_ref();
-        let mut matched = false;
-        for result in self.raw_match_results(path) {
-            if matched && !result.pattern.exception {
-                continue;
-            }
+#[cfg(feature = "unicode-normalization")]
+fn precomposed(pattern: &Pattern) -> Result<Pattern, Error> {
+    pattern.precomposed()
+}
 
-            let true_match = result.matched && (!result.pattern.dir_only || is_dir);
-            if !true_match {
-                continue;
-            }
+#[cfg(not(feature = "unicode-normalization"))]
+fn precomposed(pattern: &Pattern) -> Result<Pattern, Error> {
+    Ok(pattern.clone())
+}
+
+/// Calls `f` with this thread's scratch space, or fresh space if it's in use.
+fn with_scratch<T>(f: impl FnOnce(&mut Scratch) -> T) -> T {
+    SCRATCH.with(|scratch| match scratch.try_borrow_mut() {
+        Ok(mut scratch) => f(&mut scratch),
+        Err(_) => f(&mut Scratch::new()),
+    })
+}
 
-            matched = true_match && !result.pattern.exception;
+impl<'a> Match<'a> {
+    fn new(pattern: &'a Pattern) -> Self {
+        match pattern.exception {
+            true => Match::Exception(pattern),
+            false => Match::Pattern(pattern),
         }
+    }
 
-        matched
+    /// The pattern that matched.
+    pub fn pattern(&self) -> &'a Pattern {
+        match self {
+            Match::Pattern(pattern) | Match::Exception(pattern) => pattern,
+        }
     }
 }
 
@@ -159,6 +621,14 @@ impl FromIterator<Pattern> for Result<Matcher, Error> {
     }
 }
 
+impl Deref for Matcher {
+    type Target = [Pattern];
+
+    fn deref(&self) -> &Self::Target {
+        &self.patterns
+    }
+}
+
 impl fmt::Display for Matcher {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "[")?;
@@ -171,3 +641,132 @@ impl fmt::Display for Matcher {
         write!(f, "]")
     }
 }
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use std::borrow::Cow;
+    use std::collections::HashSet;
+
+    use serde::{de, ser, Deserialize, Serialize};
+
+    use super::{Matcher, PatternId, PatternSet};
+    use crate::pattern::compile_glob;
+    use crate::{Limits, Pattern};
+
+    /// The version of the format below. Formats with other versions are
+    /// rejected rather than misread.
+    const VERSION: u32 = 1;
+
+    /// A [`PatternSet`] as serialized. A [`Matcher`] is serialized as the set
+    /// it was compiled from and recompiled when deserialized.
+    #[derive(Serialize, Deserialize)]
+    struct Repr<'a> {
+        version: u32,
+        next_id: u64,
+        #[serde(default)]
+        precompose: bool,
+        #[serde(default)]
+        limits: Limits,
+        #[serde(borrow)]
+        patterns: Vec<PatternRepr<'a>>,
+    }
+
+    /// A [`Pattern`] as serialized: its compiled glob, root and flags, so that
+    /// patterns are restored exactly, even those without a source text.
+    #[derive(Serialize, Deserialize)]
+    struct PatternRepr<'a> {
+        id: u64,
+        #[serde(borrow)]
+        glob: Cow<'a, str>,
+        #[serde(borrow)]
+        root: Cow<'a, str>,
+        #[serde(default)]
+        exception: bool,
+        #[serde(default)]
+        dir_only: bool,
+        #[serde(default)]
+        rooted: bool,
+    }
+
+    impl<'a> Repr<'a> {
+        fn new(
+            ids: &[PatternId],
+            patterns: &'a [Pattern],
+            next_id: u64,
+            precompose: bool,
+            limits: Limits,
+        ) -> Self {
+            let patterns = ids.iter().zip(patterns).map(|(id, pattern)| PatternRepr {
+                id: id.0,
+                glob: pattern.glob().into(),
+                root: pattern.root().into(),
+                exception: pattern.exception,
+                dir_only: pattern.dir_only,
+                rooted: pattern.rooted,
+            });
+
+            Repr { version: VERSION, next_id, precompose, limits, patterns: patterns.collect() }
+        }
+
+        fn into_set<E: de::Error>(self) -> Result<PatternSet, E> {
+            if self.version != VERSION {
+                return Err(E::custom(format!("unsupported pattern set version {}", self.version)));
+            }
+
+            let mut set = PatternSet {
+                next_id: self.next_id,
+                precompose: self.precompose,
+                limits: self.limits,
+                ..PatternSet::default()
+            };
+
+            let mut seen = HashSet::with_capacity(self.patterns.len());
+            for repr in self.patterns {
+                let id = PatternId(repr.id);
+                if id.0 >= set.next_id || !seen.insert(id) {
+                    return Err(E::custom(format!("invalid or duplicate pattern ID {}", id.0)));
+                }
+
+                let pattern = Pattern {
+                    glob: compile_glob(&repr.glob).map_err(E::custom)?,
+                    root: repr.root.into(),
+                    exception: repr.exception,
+                    dir_only: repr.dir_only,
+                    rooted: repr.rooted,
+                };
+
+                set.ids.push(id);
+                set.patterns.push(pattern);
+            }
+
+            crate::pattern::intern_roots(&mut set.patterns);
+            Ok(set)
+        }
+    }
+
+    impl ser::Serialize for PatternSet {
+        fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            Repr::new(&self.ids, &self.patterns, self.next_id, self.precompose, self.limits)
+                .serialize(serializer)
+        }
+    }
+
+    impl<'de> de::Deserialize<'de> for PatternSet {
+        fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Repr::deserialize(deserializer)?.into_set()
+        }
+    }
+
+    impl ser::Serialize for Matcher {
+        fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            Repr::new(&self.ids, &self.patterns, self.next_id, self.precompose, self.limits)
+                .serialize(serializer)
+        }
+    }
+
+    impl<'de> de::Deserialize<'de> for Matcher {
+        fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            PatternSet::deserialize(deserializer)?.into_matcher().map_err(de::Error::custom)
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::{Match, Matcher, Pattern};
+
+/// An entry in an [`Lru`]: a directory's verdict and its neighbors in the
+/// recency list.
+#[derive(Debug)]
+struct Slot {
+    dir: PathBuf,
+    excluded: bool,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+/// A map from directories to verdicts holding at most `capacity` entries,
+/// evicting the least recently used entry to make room for a new one.
+#[derive(Debug)]
+struct Lru {
+    capacity: usize,
+    map: HashMap<PathBuf, usize>,
+    slots: Vec<Slot>,
+    /// The most and least recently used slots.
+    head: Option<usize>,
+    tail: Option<usize>,
+}
+
+impl Lru {
+    fn new(capacity: usize) -> Lru {
+        Lru { capacity, map: HashMap::new(), slots: vec![], head: None, tail: None }
+    }
+
+    fn unlink(&mut self, i: usize) {
+        let (prev, next) = (self.slots[i].prev, self.slots[i].next);
+        match prev {
+            Some(prev) => self.slots[prev].next = next,
+            None => self.head = next,
+        }
+
+        match next {
+            Some(next) => self.slots[next].prev = prev,
+            None => self.tail = prev,
+        }
+    }
+
+    fn link_front(&mut self, i: usize) {
+        self.slots[i].prev = None;
+        self.slots[i].next = self.head;
+        match self.head {
+            Some(head) => self.slots[head].prev = Some(i),
+            None => self.tail = Some(i),
+        }
+
+        self.head = Some(i);
+    }
+
+    fn get(&mut self, dir: &Path) -> Option<bool> {
+        let i = *self.map.get(dir)?;
+        self.unlink(i);
+        self.link_front(i);
+        Some(self.slots[i].excluded)
+    }
+
+    fn insert(&mut self, dir: &Path, excluded: bool) {
+        if let Some(&i) = self.map.get(dir) {
+            self.slots[i].excluded = excluded;
+            self.unlink(i);
+            self.link_front(i);
+            return;
+        }
+
+        let i = if self.slots.len() < self.capacity {
+            self.slots.push(Slot { dir: dir.to_path_buf(), excluded, prev: None, next: None });
+            self.slots.len() - 1
+        } else {
+            let Some(i) = self.tail else { return };
+            self.unlink(i);
+            let old = std::mem::replace(&mut self.slots[i].dir, dir.to_path_buf());
+            self.map.remove(&old);
+            self.slots[i].excluded = excluded;
+            i
+        };
+
+        self.map.insert(dir.to_path_buf(), i);
+        self.link_front(i);
+    }
+
+    fn clear(&mut self) {
+        *self = Lru::new(self.capacity);
+    }
+}
+
+/// A [`Matcher`] that applies git's rule that nothing below an excluded
+/// directory can be re-included, remembering the verdicts of recently seen
+/// directories so that queries sharing ancestors don't re-evaluate them.
+///
+/// The memo holds at most `capacity` directories, evicting the least recently
+/// used. A `CachedMatcher` is `Sync`: the memo is shared by every thread.
+#[derive(Debug)]
+pub struct CachedMatcher {
+    matcher: Matcher,
+    memo: Mutex<Lru>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CachedMatcher {
+    /// Wraps `matcher`, remembering the verdicts of at most `capacity`
+    /// directories. With a `capacity` of `0`, nothing is remembered.
+    pub fn new(matcher: Matcher, capacity: usize) -> CachedMatcher {
+        CachedMatcher {
+            matcher,
+            memo: Mutex::new(Lru::new(capacity)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns `true` if `path` is excluded: if it, or any directory above
+    /// it, is matched by [`Matcher::matches()`].
+    pub fn matches<P: AsRef<Path>>(&self, path: P, is_dir: bool) -> bool {
+        let path = path.as_ref();
+        match path.parent() {
+            Some(parent) if self.dir_excluded(parent) => true,
+            _ if is_dir => self.dir_excluded(path),
+            _ => self.matcher.matches(path, false),
+        }
+    }
+
+    /// Whether the directory `dir`, or any directory above it, is matched.
+    fn dir_excluded(&self, dir: &Path) -> bool {
+        if dir.file_name().is_none() {
+            return false;
+        }
+
+        // Find the nearest remembered ancestor, then decide the directories
+        // below it from the top down.
+        let mut pending = vec![];
+        let mut excluded = false;
+        {
+            let mut memo = self.memo.lock().unwrap_or_else(|e| e.into_inner());
+            for ancestor in dir.ancestors().take_while(|a| a.file_name().is_some()) {
+                if let Some(verdict) = memo.get(ancestor) {
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    excluded = verdict;
+                    break;
+                }
+
+                pending.push(ancestor);
+            }
+        }
+
+        if pending.is_empty() {
+            return excluded;
+        }
+
+        // Matching happens without the lock, so other threads aren't held up.
+        let mut verdicts = Vec::with_capacity(pending.len());
+        for &ancestor in pending.iter().rev() {
+            excluded = excluded || self.matcher.matches(ancestor, true);
+            verdicts.push((ancestor, excluded));
+        }
+
+        self.misses.fetch_add(verdicts.len() as u64, Ordering::Relaxed);
+        let mut memo = self.memo.lock().unwrap_or_else(|e| e.into_inner());
+        for (ancestor, verdict) in verdicts {
+            memo.insert(ancestor, verdict);
+        }
+
+        excluded
+    }
+
+    /// The number of directory verdicts found in the memo.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The number of directory verdicts that had to be computed.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Forgets every remembered verdict and resets the counters.
+    pub fn clear(&self) {
+        self.memo.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+
+    /// The wrapped matcher.
+    pub fn matcher(&self) -> &Matcher {
+        &self.matcher
+    }
+
+    /// Unwraps the matcher, discarding the memo.
+    pub fn into_inner(self) -> Matcher {
+        self.matcher
+    }
+}
+
+/// The patterns excluding the directories above paths below `top`, for
+/// deciding many paths in one tree by git's rule that nothing below an
+/// excluded directory can be re-included. Unlike [`CachedMatcher`], every
+/// verdict is remembered.
+#[derive(Debug)]
+pub(crate) struct Exclusions<'a> {
+    matcher: &'a Matcher,
+    top: PathBuf,
+    /// Directory -> the pattern excluding it or a directory above it.
+    dirs: HashMap<PathBuf, Option<&'a Pattern>>,
+}
+
+impl<'a> Exclusions<'a> {
+    /// Decides paths below `top` with `matcher`. Directories above, and
+    /// including, `top` are never excluded.
+    pub fn new(matcher: &'a Matcher, top: &Path) -> Exclusions<'a> {
+        Exclusions { matcher, top: top.to_path_buf(), dirs: HashMap::new() }
+    }
+
+    /// The pattern excluding the directory `dir` or a directory above it.
+    fn excluding(&mut self, dir: &Path) -> Option<&'a Pattern> {
+        if dir.file_name().is_none() || dir == self.top || !dir.starts_with(&self.top) {
+            return None;
+        }
+
+        if let Some(&verdict) = self.dirs.get(dir) {
+            return verdict;
+        }
+
+        let verdict = dir.parent().and_then(|parent| self.excluding(parent)).or_else(|| {
+            match self.matcher.find_match(dir, true) {
+                Some(Match::Pattern(pattern)) => Some(pattern),
+                _ => None,
+            }
+        });
+
+        self.dirs.insert(dir.to_path_buf(), verdict);
+        verdict
+    }
+
+    /// The pattern excluding a directory above `path`, if there is one.
+    pub fn excluding_parent(&mut self, path: &Path) -> Option<&'a Pattern> {
+        path.parent().and_then(|parent| self.excluding(parent))
+    }
+
+    /// Like [`Matcher::find_match()`] but, if a directory above `path` is
+    /// excluded, the pattern excluding it.
+    pub fn find_match(&mut self, path: &Path, is_dir: bool) -> Option<Match<'a>> {
+        match self.excluding_parent(path) {
+            Some(pattern) => Some(Match::Pattern(pattern)),
+            None => self.matcher.find_match(path, is_dir),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::pattern::{is_literal, Shape};
+use crate::{Error, Pattern, PatternId, PatternSet};
+
+/// Later patterns that match every path some earlier pattern might match.
+#[derive(Default)]
+struct Subsumers<'a> {
+    globs: HashSet<(&'a str, bool)>,
+    everything: Vec<(&'a str, bool)>,
+    basenames: HashMap<&'a str, Vec<(&'a str, bool)>>,
+    suffixes: Vec<(&'a str, &'a str, bool)>,
+}
+
+impl<'a> Subsumers<'a> {
+    fn add(&mut self, pattern: &'a Pattern) {
+        let dir_only = pattern.dir_only;
+        self.globs.insert((pattern.glob.glob(), dir_only));
+        match pattern.shape() {
+            Shape::Everything { prefix } => self.everything.push((prefix, dir_only)),
+            Shape::Basename { prefix, name } => {
+                self.basenames.entry(name).or_default().push((prefix, dir_only));
+            }
+            Shape::Suffix { prefix, suffix } => self.suffixes.push((prefix, suffix, dir_only)),
+            Shape::Literal(_) | Shape::Complex => {}
+        }
+    }
+
+    /// Whether every path `pattern` truly matches is also truly matched by
+    /// one of the subsumers.
+    fn subsume(&self, pattern: &Pattern) -> bool {
+        let glob = pattern.glob.glob();
+        let literal_prefix = &glob[..glob.find(['*', '?', '[', '{', '\\']).unwrap_or(glob.len())];
+        let name = glob.rsplit('/').next().unwrap_or(glob);
+        let applies = |&dir_only: &bool| !dir_only || pattern.dir_only;
+
+        self.globs.contains(&(glob, false))
+            || (pattern.dir_only && self.globs.contains(&(glob, true)))
+            || self.everything.iter()
+                .any(|(prefix, d)| applies(d) && literal_prefix.starts_with(prefix))
+            || (is_literal(name) && self.basenames.get(name).is_some_and(|subsumers| {
+                subsumers.iter().any(|(prefix, d)| applies(d) && literal_prefix.starts_with(prefix))
+            }))
+            || (!name.contains(['[', '{', '\\']) && self.suffixes.iter().any(|(prefix, suffix, d)| {
+                applies(d) && name.ends_with(suffix) && literal_prefix.starts_with(prefix)
+            }))
+    }
+}
+
+/// The part of a non-rooted pattern that can be merged with others into an
+/// alternation, along with the pattern's merge group.
+fn mergeable(pattern: &Pattern) -> Option<((&str, bool, bool), &str)> {
+    if pattern.rooted {
+        return None;
+    }
+
+    let (prefix, part) = match pattern.shape() {
+        Shape::Basename { prefix, name } => (prefix, name),
+        Shape::Suffix { prefix, suffix } => (prefix, suffix.strip_prefix('.')?),
+        _ => return None,
+    };
+
+    if part.contains([',', '/', '!']) {
+        return None;
+    }
+
+    let is_suffix = matches!(pattern.shape(), Shape::Suffix { .. });
+    Some(((prefix, pattern.dir_only, is_suffix), part))
+}
+
+/// Merges the mergeable patterns in `run`, a run of patterns with the same
+/// polarity that can thus be freely reordered. Each merged pattern takes the
+/// place, and ID, of the first pattern in its group.
+fn merge_run(run: &[(PatternId, Pattern)]) -> Result<Vec<(PatternId, Pattern)>, Error> {
+    let mut groups: Vec<Vec<&str>> = vec![];
+    let mut group_index: HashMap<_, usize> = HashMap::new();
+    let mut slots: Vec<(&(PatternId, Pattern), Option<usize>)> = vec![];
+    for slot in run {
+        match mergeable(&slot.1) {
+            Some((key, part)) => match group_index.get(&key) {
+                Some(&i) => groups[i].push(part),
+                None => {
+                    group_index.insert(key, groups.len());
+                    slots.push((slot, Some(groups.len())));
+                    groups.push(vec![part]);
+                }
+            },
+            None => slots.push((slot, None)),
+        }
+    }
+
+    let mut merged = Vec::with_capacity(slots.len());
+    for ((id, first), group) in slots {
+        let parts = match group {
+            Some(i) if groups[i].len() > 1 => &groups[i],
+            _ => {
+                merged.push((*id, first.clone()));
+                continue;
+            }
+        };
+
+        let body = match first.shape() {
+            Shape::Suffix { .. } => format!("*.{{{}}}", parts.join(",")),
+            _ => format!("{{{}}}", parts.join(",")),
+        };
+
+        let text = format!(
+            "{}{}{}",
+            if first.exception { "!" } else { "" },
+            body,
+            if first.dir_only { "/" } else { "" },
+        );
+
+        merged.push((*id, Pattern::new(&text, &*first.root)?));
+    }
+
+    Ok(merged)
+}
+
+impl PatternSet {
+    /// Removes patterns that can never decide whether a path is matched and
+    /// merges simple patterns into alternations, such as `*.o` and `*.obj`
+    /// into `*.{o,obj}`, without changing what the set matches.
+    ///
+    /// A pattern is removed if a later pattern matches every path it matches,
+    /// as it is then never the last matching pattern, or if it is an
+    /// exception that precedes every non-exception pattern. Patterns are
+    /// merged only within runs of patterns with the same polarity.
+    pub fn minimize(&mut self) -> Result<&mut Self, Error> {
+        let patterns = &self.patterns;
+        let mut keep = vec![true; patterns.len()];
+        let mut subsumers = Subsumers::default();
+        for (i, pattern) in patterns.iter().enumerate().rev() {
+            keep[i] = !subsumers.subsume(pattern);
+            subsumers.add(pattern);
+        }
+
+        let mut kept = self.ids.iter()
+            .zip(patterns)
+            .zip(keep)
+            .filter(|(_, keep)| *keep)
+            .map(|((&id, pattern), _)| (id, pattern.clone()))
+            .skip_while(|(_, pattern)| pattern.exception)
+            .peekable();
+
+        let mut minimized = vec![];
+        while let Some(first) = kept.next() {
+            let exception = first.1.exception;
+            let mut run = vec![first];
+            while let Some(slot) = kept.next_if(|(_, p)| p.exception == exception) {
+                run.push(slot);
+            }
+
+            minimized.extend(merge_run(&run)?);
+        }
+
+        (self.ids, self.patterns) = minimized.into_iter().unzip();
+        Ok(self)
+    }
+}
@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
 use std::path::{Component, Path, PathBuf};
 
 #[cfg(windows)]
@@ -39,9 +40,11 @@ pub trait PathExt: AsRef<Path> {
     /// needed. That is, this is `base.join(self).dedot()` but more efficient.
     fn dedot_from<B: AsRef<Path>>(&self, base: B) -> PathBuf {
         let (base, path) = (base.as_ref(), self.as_ref());
-        let components = path.is_absolute()
-            .then(|| path.components().chain(Path::new("").components()))
-            .unwrap_or_else(|| base.components().chain(path.components()));
+        let components = if path.is_absolute() {
+            path.components().chain(Path::new("").components())
+        } else {
+            base.components().chain(path.components())
+        };
 
         dedot_components(components)
     }
@@ -71,7 +74,7 @@ impl<P: AsRef<Path>> PathExt for P {
         if prefix.len() > path.len() || prefix != &path[..prefix.len()] {
             None
         } else {
-            Some(&Path::new(OsStr::from_bytes(&path[prefix.len()..])))
+            Some(Path::new(OsStr::from_bytes(&path[prefix.len()..])))
         }
     }
 }
@@ -123,6 +126,71 @@ impl<P: AsRef<Path>> PathExt for P {
     }
 }
 
+/// The bytes of `path` normalized to use `/` as a separator everywhere.
+///
+/// On Unix, this is zero cost and exact. On other platforms, this lossily
+/// converts the path to UTF-8 as [`PathExt::to_normalized_string_lossy()`].
+#[cfg(any(unix, target_os = "wasi"))]
+pub(crate) fn normalized_bytes(path: &Path) -> Cow<'_, [u8]> {
+    Cow::Borrowed(path.as_os_str().as_bytes())
+}
+
+#[cfg(not(any(unix, target_os = "wasi")))]
+pub(crate) fn normalized_bytes(path: &Path) -> Cow<'_, [u8]> {
+    match path.to_normalized_string_lossy() {
+        Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+        Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+    }
+}
+
+/// The bytes of `string`: exact on Unix, and lossily converted to UTF-8 on
+/// other platforms.
+#[cfg(any(unix, target_os = "wasi"))]
+pub(crate) fn os_str_bytes(string: &OsStr) -> Cow<'_, [u8]> {
+    Cow::Borrowed(string.as_bytes())
+}
+
+#[cfg(not(any(unix, target_os = "wasi")))]
+pub(crate) fn os_str_bytes(string: &OsStr) -> Cow<'_, [u8]> {
+    match string.to_string_lossy() {
+        Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+        Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+    }
+}
+
+/// The inverse of [`os_str_bytes()`]. Returns `None` if `bytes` isn't valid
+/// UTF-8 on platforms other than Unix.
+#[cfg(any(unix, target_os = "wasi"))]
+pub(crate) fn os_string_from_bytes(bytes: Vec<u8>) -> Option<OsString> {
+    #[cfg(unix)]
+    use std::os::unix::ffi::OsStringExt;
+
+    #[cfg(target_os = "wasi")]
+    use std::os::wasi::ffi::OsStringExt;
+
+    Some(OsString::from_vec(bytes))
+}
+
+#[cfg(not(any(unix, target_os = "wasi")))]
+pub(crate) fn os_string_from_bytes(bytes: Vec<u8>) -> Option<OsString> {
+    String::from_utf8(bytes).ok().map(OsString::from)
+}
+
+/// The Unicode Normalization Form C of `string`, as git's
+/// `core.precomposeUnicode` uses for paths, or `None` if `string` is already
+/// in that form.
+#[cfg(feature = "unicode-normalization")]
+pub(crate) fn precompose(string: &str) -> Option<String> {
+    use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+    if string.is_ascii() || is_nfc_quick(string.chars()) == IsNormalized::Yes {
+        return None;
+    }
+
+    let precomposed: String = string.nfc().collect();
+    (precomposed != string).then_some(precomposed)
+}
+
 fn dedot_components<'c>(components: impl Iterator<Item = Component<'c>>) -> PathBuf {
     use std::path::Component::*;
 
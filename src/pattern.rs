@@ -1,7 +1,9 @@
 use std::fmt;
+use std::collections::HashSet;
 use std::borrow::Cow;
 use std::ops::Deref;
 use std::path::Path;
+use std::sync::Arc;
 
 use crate::{Error, PatternSet, PathExt, Matcher};
 
@@ -10,12 +12,90 @@ use globset::{GlobBuilder, Glob};
 #[derive(Debug, Clone)]
 pub struct Pattern {
     pub(crate) glob: Glob,
-    pub(crate) root: String,
+    /// Shared by patterns with the same root. See [`intern_roots()`].
+    pub(crate) root: Arc<str>,
     pub(crate) exception: bool,
     pub(crate) dir_only: bool,
     pub(crate) rooted: bool,
 }
 
+/// The structure of a pattern's glob, used to recognize patterns that can be
+/// matched or reasoned about without the glob engine. Every `prefix` is empty
+/// or a literal ending in `/`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Shape<'a> {
+    /// `{prefix}**` or `{prefix}**/*`: matches any path under `prefix`.
+    Everything { prefix: &'a str },
+    /// `{prefix}**/{name}`: matches paths under `prefix` named `name`.
+    Basename { prefix: &'a str, name: &'a str },
+    /// `{prefix}**/*{suffix}`: matches paths under `prefix` whose name ends
+    /// with `suffix`.
+    Suffix { prefix: &'a str, suffix: &'a str },
+    /// A glob without special characters: matches only itself.
+    Literal(&'a str),
+    /// Anything else.
+    Complex,
+}
+
+/// Compiles `glob` as every pattern's glob is compiled: with `*` and `?`
+/// never matching `/`.
+pub(crate) fn compile_glob(glob: &str) -> Result<Glob, globset::Error> {
+    GlobBuilder::new(glob).literal_separator(true).build()
+}
+
+/// Returns `true` if `glob` contains no characters special to the glob engine.
+pub(crate) fn is_literal(glob: &str) -> bool {
+    !glob.contains(['*', '?', '[', ']', '{', '}', '\\'])
+}
+
+/// The globs that `glob` is the union of if it has one alternation of
+/// literals, like `**/*.{o,obj}` of `**/*.o` and `**/*.obj`.
+pub(crate) fn alternatives(glob: &str) -> Option<Vec<String>> {
+    let open = glob.find('{')?;
+    let close = open + glob[open..].find('}')?;
+    let (head, body, tail) = (&glob[..open], &glob[open + 1..close], &glob[close + 1..]);
+    if glob.contains('\\') || head.contains('}') || tail.contains(['{', '}']) {
+        return None;
+    }
+
+    let literal = |part: &str| !part.is_empty() && !part.contains('/') && is_literal(part);
+    body.split(',')
+        .map(|part| literal(part).then(|| format!("{}{}{}", head, part, tail)))
+        .collect()
+}
+
+impl<'a> Shape<'a> {
+    pub(crate) fn of(glob: &'a str) -> Shape<'a> {
+        if is_literal(glob) {
+            return Shape::Literal(glob);
+        }
+
+        let Some((prefix, rest)) = glob.split_once("**") else {
+            return Shape::Complex;
+        };
+
+        if !is_literal(prefix) || !(prefix.is_empty() || prefix.ends_with('/')) {
+            return Shape::Complex;
+        }
+
+        match rest {
+            "" | "/*" => Shape::Everything { prefix },
+            _ => match rest.strip_prefix('/') {
+                Some(name) if !name.contains('/') && is_literal(name) && !name.is_empty() => {
+                    Shape::Basename { prefix, name }
+                }
+                Some(name) => match name.strip_prefix('*') {
+                    Some(suffix) if !suffix.contains('/') && is_literal(suffix) && !suffix.is_empty() => {
+                        Shape::Suffix { prefix, suffix }
+                    }
+                    _ => Shape::Complex,
+                },
+                None => Shape::Complex,
+            }
+        }
+    }
+}
+
 struct RawPattern(str);
 
 enum Prefix {
@@ -32,7 +112,7 @@ impl RawPattern {
 
     fn prefix(&self) -> Prefix {
         let bytes = self.0.as_bytes();
-        match (bytes.get(0), bytes.get(1)) {
+        match (bytes.first(), bytes.get(1)) {
             (Some(b'/'), _) => Prefix::Root,
             (Some(b'!'), Some(b'/')) => Prefix::NegativeRoot,
             (Some(b'!'), _) => Prefix::Negative,
@@ -69,9 +149,11 @@ impl RawPattern {
 impl Pattern {
     pub fn new<P: AsRef<Path>>(pattern: &str, root: P) -> Result<Self, Error> {
         let pattern = RawPattern::new(pattern);
-        let mut glob: Cow<'_, str> = (pattern.rooted() || pattern.path().starts_with("**/"))
-            .then(|| pattern.path().into())
-            .unwrap_or_else(|| format!("**/{}", pattern.path()).into());
+        let mut glob: Cow<'_, str> = if pattern.rooted() || pattern.path().starts_with("**/") {
+            pattern.path().into()
+        } else {
+            format!("**/{}", pattern.path()).into()
+        };
 
         let root = root.to_normalized_string_lossy();
         if !root.is_empty() {
@@ -87,7 +169,7 @@ impl Pattern {
         }
 
         Ok(Pattern {
-            glob: GlobBuilder::new(&glob).literal_separator(true).build()?,
+            glob: compile_glob(&glob)?,
             root: root.into(),
             exception: pattern.negative(),
             dir_only: pattern.dir_only(),
@@ -95,27 +177,87 @@ impl Pattern {
         })
     }
 
-    // Will go _below_ the root.
+    /// Resolves the `.` and `..` components in this pattern's glob, root
+    /// included. Unlike [`Pattern::checked_dedotted()`], the result may climb
+    /// above, and so match paths outside of, the root.
     pub fn rootful_dedotted(self) -> Self {
         let dedotted = self.glob.glob().dedot();
         let glob = dedotted.to_normalized_string_lossy();
         Pattern {
-            glob: GlobBuilder::new(&glob).literal_separator(true).build().expect("valid => valid"),
+            glob: compile_glob(&glob).expect("valid => valid"),
             ..self
         }
     }
 
+    /// Resolves the `.` and `..` components in this pattern's glob below its
+    /// root. A `..` that would climb above the root is dropped, so `a/../../x`
+    /// becomes `x`; see [`Pattern::checked_dedotted()`] to reject it instead.
     pub fn dedotted(self) -> Self {
         let glob = self.glob.glob();
-        let glob = glob.strip_prefix(&self.root).unwrap_or(glob).dedot();
+        let glob = glob.strip_prefix(&*self.root).unwrap_or(glob).dedot();
         let glob = glob.to_normalized_string_lossy();
-        let pattern = Pattern::new(&glob, &self.root).expect("valid => valid");
+        let pattern = Pattern::new(&glob, &*self.root).expect("valid => valid");
         Pattern {
             glob: pattern.glob,
             ..self
         }
     }
 
+    /// Like [`Pattern::dedotted()`] but fails with [`Error::EscapesRoot`] if
+    /// the pattern climbs above its root.
+    pub fn checked_dedotted(self) -> Result<Self, Error> {
+        if self.escapes_root() {
+            return Err(Error::EscapesRoot { pattern: self.to_string(), root: self.root.to_string() });
+        }
+
+        Ok(self.dedotted())
+    }
+
+    /// Returns `true` if this pattern can match paths outside of its root:
+    /// if its glob doesn't start with the root or, following its `..`
+    /// components, climbs above it. A `**` is taken to match no components,
+    /// and a brace, class or `?` component that can match `..`, like `{..}`,
+    /// is taken to be one.
+    pub fn escapes_root(&self) -> bool {
+        let glob = self.glob.glob();
+        let below = match glob.strip_prefix(&*self.root) {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+            Some(rest) if self.root.is_empty() || self.root.ends_with('/') => rest,
+            _ => return true,
+        };
+
+        let mut depth = 0usize;
+        for component in below.split('/') {
+            match component {
+                "" | "." | "**" => {}
+                _ if !may_be_dot_dot(component) => depth += 1,
+                _ if depth == 0 => return true,
+                _ => depth -= 1,
+            }
+        }
+
+        false
+    }
+
+    /// This pattern with its glob and root in Unicode Normalization Form C,
+    /// so that it matches paths precomposed the same way. Fails only if the
+    /// glob becomes invalid, such as a class range whose ends are reordered.
+    #[cfg(feature = "unicode-normalization")]
+    pub fn precomposed(&self) -> Result<Self, Error> {
+        use crate::pathext::precompose;
+
+        let glob = match precompose(self.glob.glob()) {
+            Some(glob) => compile_glob(&glob)?,
+            None => self.glob.clone(),
+        };
+
+        Ok(Pattern {
+            glob,
+            root: precompose(&self.root).map(Arc::from).unwrap_or_else(|| self.root.clone()),
+            ..self.clone()
+        })
+    }
+
     pub fn with_root<P: AsRef<Path>>(&self, root: P) -> Result<Self, Error> {
         Pattern::new(&self.to_string(), root.as_ref())
     }
@@ -125,6 +267,22 @@ impl Pattern {
         self
     }
 
+    /// The glob this pattern compiles to, including its root.
+    pub fn glob(&self) -> &str {
+        self.glob.glob()
+    }
+
+    /// The directory this pattern is relative to, normalized to use `/` as
+    /// a separator. Patterns with equal roots parsed together share one
+    /// copy of it.
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    pub(crate) fn shape(&self) -> Shape<'_> {
+        Shape::of(self.glob.glob())
+    }
+
     pub fn into_set(self) -> PatternSet {
         [self].into_iter().collect()
     }
@@ -134,6 +292,34 @@ impl Pattern {
     }
 }
 
+/// Returns `true` if the glob `component`, one component of a pattern, may
+/// match `..`: if it's `..` or a brace, class or `?` that can match it.
+/// Components that don't compile on their own, having been split in the
+/// middle of a brace, may match anything.
+fn may_be_dot_dot(component: &str) -> bool {
+    if component == ".." {
+        return true;
+    }
+
+    if !component.contains(['{', '}', '[', ']', '?']) {
+        return false;
+    }
+
+    let dot_dot = globset::Candidate::from_bytes(b"..");
+    compile_glob(component).map_or(true, |glob| glob.compile_matcher().is_match_candidate(&dot_dot))
+}
+
+/// Makes patterns with equal roots share one allocation of it.
+pub(crate) fn intern_roots<'p, I: IntoIterator<Item = &'p mut Pattern>>(patterns: I) {
+    let mut roots: HashSet<Arc<str>> = HashSet::new();
+    for pattern in patterns {
+        match roots.get(&*pattern.root) {
+            Some(root) => pattern.root = root.clone(),
+            None => { roots.insert(pattern.root.clone()); }
+        }
+    }
+}
+
 impl Deref for RawPattern {
     type Target = str;
 
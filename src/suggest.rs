@@ -0,0 +1,137 @@
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+
+use crate::{CachedMatcher, Error, PathExt, Pattern, PatternSet};
+
+/// An example path: its normalized text, without a trailing slash, and
+/// whether it names a directory.
+struct Example {
+    path: String,
+    is_dir: bool,
+}
+
+impl Example {
+    fn new<P: AsRef<Path>>(path: P) -> Example {
+        let is_dir = path.has_trailing_slash();
+        let path = path.to_normalized_string_lossy();
+        let path = path.trim_end_matches('/').trim_start_matches("./");
+        Example { path: path.into(), is_dir }
+    }
+}
+
+/// Escapes `name` so that it is matched literally when used in a pattern.
+fn escape(name: &str) -> String {
+    let mut escaped = globset::escape(name);
+    if escaped.starts_with(['!', '#']) {
+        escaped.insert(0, '\\');
+    }
+
+    if escaped.ends_with(' ') {
+        escaped.insert(escaped.len() - 1, '\\');
+    }
+
+    escaped
+}
+
+/// Candidate patterns for `example`, from most to least general. Directories
+/// are excluded as a whole, as git excludes everything below them.
+fn candidates(example: &Example) -> Vec<String> {
+    let components: Vec<&str> = example.path.split('/').filter(|c| !c.is_empty()).collect();
+    let Some((name, parents)) = components.split_last() else {
+        return vec![];
+    };
+
+    let mut candidates = vec![];
+    let dir_suffix = if example.is_dir { "/" } else { "" };
+    if let Some((_, ext)) = name.rsplit_once('.').filter(|(stem, _)| !stem.is_empty()) {
+        candidates.push(format!("*.{}{}", escape(ext), dir_suffix));
+    }
+
+    candidates.push(format!("{}{}", escape(name), dir_suffix));
+    for (i, parent) in parents.iter().enumerate() {
+        candidates.push(format!("{}/", escape(parent)));
+        let anchored: Vec<String> = parents[..=i].iter().map(|c| escape(c)).collect();
+        candidates.push(format!("/{}/", anchored.join("/")));
+    }
+
+    let anchored: Vec<String> = components.iter().map(|c| escape(c)).collect();
+    candidates.push(format!("/{}{}", anchored.join("/"), dir_suffix));
+    candidates
+}
+
+impl PatternSet {
+    /// Proposes a small set of patterns that matches every path in `ignored`
+    /// and none of the paths in `tracked`.
+    ///
+    /// Paths are relative to the directory the patterns are for, and paths
+    /// with a trailing slash are considered directories. Candidates range
+    /// from extensions and names to anchored paths; they are chosen greedily
+    /// by how many paths they cover. Every candidate, and the final set, is
+    /// verified with a [`CachedMatcher`], which, like git, ignores everything
+    /// below an ignored directory. If some ignored path can't be separated
+    /// from the tracked paths, such as when it is also tracked, returns
+    /// [`Error::Inseparable`].
+    ///
+    /// [`Formatter::format_pattern()`](crate::Formatter::format_pattern())
+    /// writes the suggested patterns as `.gitignore` lines.
+    pub fn suggest<I, T>(ignored: I, tracked: T) -> Result<PatternSet, Error>
+        where I: IntoIterator, I::Item: AsRef<Path>,
+              T: IntoIterator, T::Item: AsRef<Path>,
+    {
+        let ignored: Vec<Example> = ignored.into_iter().map(Example::new).collect();
+        let tracked: Vec<Example> = tracked.into_iter().map(Example::new).collect();
+
+        // Every valid candidate along with the indices of the paths it covers.
+        let mut seen = HashSet::new();
+        let mut valid: Vec<(String, BTreeSet<usize>)> = vec![];
+        for example in &ignored {
+            for candidate in candidates(example) {
+                if !seen.insert(candidate.clone()) {
+                    continue;
+                }
+
+                let matcher = CachedMatcher::new(Pattern::new(&candidate, "")?.into_matcher()?, 64);
+                if tracked.iter().any(|t| matcher.matches(&t.path, t.is_dir)) {
+                    continue;
+                }
+
+                let covered = ignored.iter()
+                    .enumerate()
+                    .filter(|(_, e)| matcher.matches(&e.path, e.is_dir))
+                    .map(|(i, _)| i)
+                    .collect();
+
+                valid.push((candidate, covered));
+            }
+        }
+
+        let mut uncovered: BTreeSet<usize> = (0..ignored.len()).collect();
+        let mut suggestion = PatternSet::new();
+        while let Some(&first) = uncovered.first() {
+            let best = valid.iter()
+                .enumerate()
+                .map(|(i, (_, covered))| (i, covered.intersection(&uncovered).count()))
+                .filter(|(_, count)| *count > 0)
+                .rev()
+                .max_by_key(|(_, count)| *count);
+
+            let Some((best, _)) = best else {
+                return Err(Error::Inseparable { path: ignored[first].path.clone() });
+            };
+
+            let (candidate, covered) = &valid[best];
+            uncovered.retain(|i| !covered.contains(i));
+            suggestion.add(Pattern::new(candidate, "")?);
+        }
+
+        let matcher = suggestion.iter().cloned().collect::<Result<crate::Matcher, _>>()?;
+        let matcher = CachedMatcher::new(matcher, 64);
+        let unmatched = ignored.iter().find(|e| !matcher.matches(&e.path, e.is_dir));
+        let matched = tracked.iter().find(|t| matcher.matches(&t.path, t.is_dir));
+        if let Some(example) = unmatched.or(matched) {
+            return Err(Error::Inseparable { path: example.path.clone() });
+        }
+
+        Ok(suggestion)
+    }
+}
@@ -0,0 +1,29 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Recursively lists every entry below `dir`, in a deterministic order, along
+/// with whether it is a directory. Symbolic links are not followed, and `.git`
+/// directories are skipped.
+pub(crate) fn walk<P: AsRef<Path>>(dir: P) -> io::Result<Vec<(PathBuf, bool)>> {
+    fn visit(dir: &Path, entries: &mut Vec<(PathBuf, bool)>) -> io::Result<()> {
+        let mut children = std::fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+        children.sort_by_key(|entry| entry.file_name());
+        for child in children {
+            let is_dir = child.file_type()?.is_dir();
+            if is_dir && child.file_name() == ".git" {
+                continue;
+            }
+
+            entries.push((child.path(), is_dir));
+            if is_dir {
+                visit(&child.path(), entries)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    let mut entries = vec![];
+    visit(dir.as_ref(), &mut entries)?;
+    Ok(entries)
+}
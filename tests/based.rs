@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+
+use gitfilter::{Document, Error, PathMode};
+
+#[test]
+fn test_relative_mode() {
+    let matcher = Document::parse("*.o\n/build/\n", "").unwrap().to_matcher().unwrap();
+    let based = matcher.based("/root/scratch", PathMode::Relative);
+
+    assert_eq!(based.resolve("/root/scratch/src/a.o").unwrap(), PathBuf::from("src/a.o"));
+    assert_eq!(based.resolve("src/./x/../a.o").unwrap(), PathBuf::from("src/a.o"));
+    assert_eq!(based.resolve("/root/scratch").unwrap(), PathBuf::from(""));
+    assert!(based.matches("/root/scratch/src/a.o", false).unwrap());
+    assert!(based.matches("/root/scratch/build", true).unwrap());
+    assert!(based.matches("build", true).unwrap());
+    assert!(!based.matches("/root/scratch/src/build", true).unwrap());
+
+    // As given, the absolute path isn't `/build`, but `/bar.rs` and `bar.rs`
+    // are alike.
+    assert!(!matcher.matches("/root/scratch/build", true));
+    assert_eq!(matcher.matches("/a.o", false), matcher.matches("a.o", false));
+
+    for outside in ["/root/scratchy/a.o", "/root/a.o", "../a.o", "src/../../a.o", "/root/scratch/../a.o"] {
+        match based.matches(outside, false) {
+            Err(Error::OutsideBase { path, base }) => {
+                assert_eq!((path.as_str(), base.as_str()), (outside, "/root/scratch"));
+            }
+            result => panic!("{}: {:?}", outside, result),
+        }
+    }
+
+    let based = matcher.based("/root/scratch/", PathMode::Relative);
+    assert!(based.matches("/root/scratch/a.o", false).unwrap());
+
+    // The base is compared by component, not byte.
+    assert_eq!(based.resolve("/root//scratch/./src/a.o").unwrap(), PathBuf::from("src/a.o"));
+    assert!(matcher.based("scratch", PathMode::Relative).matches("/scratch/a.o", false).is_err());
+}
+
+#[test]
+fn test_absolute_mode() {
+    let matcher = Document::parse("*.o\n/build/\n", "/root/scratch").unwrap().to_matcher().unwrap();
+    let based = matcher.based("/root/scratch", PathMode::Absolute);
+
+    assert_eq!(based.resolve("src/a.o").unwrap(), PathBuf::from("/root/scratch/src/a.o"));
+    assert_eq!(based.base(), PathBuf::from("/root/scratch"));
+    assert!(based.matches("build", true).unwrap());
+    assert!(based.matches("./src/a.o", false).unwrap());
+    assert!(based.matches("/root/scratch/build", true).unwrap());
+    assert!(!based.matches("src/build", true).unwrap());
+    assert!(based.find_match("x/a.o", false).unwrap().is_some());
+
+    // As given, relative paths never match patterns rooted at the base.
+    assert!(!matcher.matches("build", true));
+
+    assert!(based.matches("/tmp/a.o", false).is_err());
+    assert!(based.matches("../a.o", false).is_err());
+    let error = based.find_match("/root/a.o", false).unwrap_err();
+    assert_eq!(error.to_string(), "`/root/a.o` is outside of the base directory `/root/scratch`");
+}
+
+#[test]
+#[cfg(windows)]
+fn test_windows_base() {
+    let matcher = Document::parse("/target/\n", "").unwrap().to_matcher().unwrap();
+    let based = matcher.based(r"C:\repo", PathMode::Relative);
+    assert_eq!(based.resolve(r"C:\repo\target").unwrap(), PathBuf::from("target"));
+    assert!(based.matches(r"C:\repo\target", true).unwrap());
+    assert!(based.resolve(r"C:\repository\target").is_err());
+}
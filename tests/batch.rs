@@ -0,0 +1,50 @@
+use gitfilter::{Candidate, Matcher, Pattern, Scratch};
+
+fn matcher() -> Matcher {
+    ["*.o", "!keep.o", "build/", "/target", "src/**/gen"]
+        .iter()
+        .map(|p| Pattern::new(p, "").unwrap())
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+fn paths() -> Vec<(String, bool)> {
+    let names = ["a.o", "keep.o", "build", "target", "gen", "x.rs"];
+    let dirs = ["", "src/", "src/a/", "target/"];
+    let mut paths = vec![];
+    for dir in dirs {
+        for name in names {
+            paths.push((format!("{}{}", dir, name), false));
+            paths.push((format!("{}{}", dir, name), true));
+        }
+    }
+
+    paths
+}
+
+#[test]
+fn test_matches_batch() {
+    let matcher = matcher();
+    let paths = paths();
+    let expected: Vec<bool> = paths.iter().map(|(p, d)| matcher.matches(p, *d)).collect();
+    assert!(expected.iter().any(|m| *m) && expected.iter().any(|m| !*m));
+    assert_eq!(matcher.matches_batch(paths.iter().map(|(p, d)| (p, *d))), expected);
+    assert!(matcher.matches_batch(Vec::<(&str, bool)>::new()).is_empty());
+
+    #[cfg(feature = "rayon")]
+    assert_eq!(matcher.par_matches_batch(&paths), expected);
+}
+
+#[test]
+fn test_find_matches_into() {
+    let matcher = matcher();
+    let mut scratch = Scratch::new();
+    for (path, is_dir) in paths() {
+        let expected: Vec<String> = matcher.find_matches(&path, is_dir).map(|m| m.pattern().to_string()).collect();
+        let actual: Vec<String> = matcher.find_matches_into(&Candidate::new(&path), is_dir, &mut scratch)
+            .map(|m| m.pattern().to_string())
+            .collect();
+
+        assert_eq!(actual, expected, "{:?}", path);
+    }
+}
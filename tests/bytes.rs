@@ -0,0 +1,82 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::ffi::OsStr;
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+use gitfilter::Candidate;
+
+use common::{matcher, TempDir};
+
+#[test]
+fn test_matches_bytes() {
+    let matcher = matcher("caf\u{FFFD}\n*.log\n!dir\u{FFFD}/*.log\n[!a]?.o\n");
+    let cases: &[(&[u8], bool)] = &[
+        (b"caf\xef\xbf\xbd", true),
+        (b"caf\xe9", false),
+        (b"caf\xff", false),
+        (b"dir\xfe/x.log", true),
+        (b"dir\xef\xbf\xbd/x.log", false),
+        (b"\xff\xfe.o", true),
+        (b"\xff\xfe\xfd.o", false),
+    ];
+
+    for &(path, expected) in cases {
+        assert_eq!(matcher.matches_bytes(path, false), expected, "{:?}", path);
+        assert_eq!(matcher.matches(OsStr::from_bytes(path), false), expected, "{:?}", path);
+        assert_eq!(matcher.matches_candidate(&Candidate::from_bytes(path), false), expected);
+    }
+}
+
+#[test]
+fn test_non_utf8_tree() {
+    let root = TempDir::new("bytes");
+    let names: &[&[u8]] = &[b"caf\xe9", b"caf\xff", b"caf\xef\xbf\xbd", b"x\xe9.log", b"x\xff.log"];
+    for name in names {
+        let dir = root.join(OsStr::from_bytes(b"dir\xfe"));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(root.join(OsStr::from_bytes(name)), "").unwrap();
+        fs::write(dir.join(OsStr::from_bytes(name)), "").unwrap();
+    }
+
+    // Lossily, every name here would collide with one that isn't matched.
+    let matcher = matcher("caf\u{FFFD}\nx?.log\n!dir\u{FFFD}/**\n");
+    let top = matcher.dir_state("");
+    let mut matched = vec![];
+    for entry in fs::read_dir(&root).unwrap() {
+        let entry = entry.unwrap();
+        let is_dir = entry.file_type().unwrap().is_dir();
+        let name = entry.file_name();
+        let state = matcher.descend(&top, &name, is_dir);
+        assert_eq!(state.is_match(), matcher.matches(&name, is_dir));
+        if state.is_match() {
+            matched.push(name.as_bytes().to_vec());
+        }
+
+        if !is_dir {
+            continue;
+        }
+
+        for child in fs::read_dir(entry.path()).unwrap() {
+            let child = child.unwrap().file_name();
+            let path = Path::new(&name).join(&child);
+            let is_match = matcher.matches(&path, false);
+            assert_eq!(is_match, matcher.matches_bytes(path.as_os_str().as_bytes(), false));
+            assert_eq!(matcher.descend(&state, &child, false).is_match(), is_match);
+            if is_match {
+                matched.push(path.as_os_str().as_bytes().to_vec());
+            }
+        }
+    }
+
+    matched.sort();
+    let expected: &[&[u8]] = &[
+        b"caf\xef\xbf\xbd", b"dir\xfe/caf\xef\xbf\xbd", b"dir\xfe/x\xe9.log", b"dir\xfe/x\xff.log",
+        b"x\xe9.log", b"x\xff.log",
+    ];
+
+    assert_eq!(matched, expected);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, UNIX_EPOCH};
+
+use gitfilter::StatusCache;
+
+use common::{write, TempDir};
+
+/// Sets the modification time of `path` to a distinct time in the past, so
+/// that the cache trusts it, and, if `recursive`, of everything below it.
+fn backdate(path: &Path, recursive: bool) {
+    static SECONDS: AtomicU64 = AtomicU64::new(1_000_000_000);
+
+    if recursive && path.is_dir() {
+        for entry in fs::read_dir(path).unwrap() {
+            backdate(&entry.unwrap().path(), true);
+        }
+    }
+
+    let then = UNIX_EPOCH + Duration::from_secs(SECONDS.fetch_add(1, Ordering::Relaxed));
+    fs::File::open(path).unwrap().set_modified(then).unwrap();
+}
+
+fn ignored(cache: &mut StatusCache, root: &Path) -> Vec<PathBuf> {
+    cache.status().unwrap().into_iter()
+        .filter(|status| status.ignored)
+        .map(|status| status.path.strip_prefix(root).unwrap().to_path_buf())
+        .collect()
+}
+
+#[test]
+fn test_status_cache() {
+    let root = TempDir::new("cache");
+    write(&root, ".gitignore", "*.o\n/build/\n");
+    write(&root, "src/.gitignore", "!keep.o\n");
+    write(&root, "build/.gitignore", "!out\n");
+    for file in ["src/a.rs", "src/a.o", "src/keep.o", "build/out", "docs/x.md", ".git/HEAD"] {
+        write(&root, file, "");
+    }
+
+    backdate(&root, true);
+    let mut cache = StatusCache::new(&root);
+    // Entries below an ignored directory are ignored, exceptions or not.
+    let expected = ["build", "build/.gitignore", "build/out", "src/a.o"].map(PathBuf::from);
+    assert_eq!(ignored(&mut cache, &root), expected);
+    assert_eq!(cache.refreshed(), 4);
+
+    let statuses = cache.status().unwrap();
+    assert_eq!(cache.refreshed(), 0);
+    assert_eq!(statuses.len(), 11);
+    assert!(statuses.iter().all(|s| !s.path.starts_with(root.join(".git"))));
+
+    // A saved cache is as good as the original.
+    let file = root.join(".git/gitfilter-cache");
+    cache.save(&file).unwrap();
+    let mut cache = StatusCache::load(&root, &file).unwrap();
+    assert_eq!(cache.status().unwrap(), statuses);
+    assert_eq!(cache.refreshed(), 0);
+    assert_eq!(StatusCache::load(root.join("src"), &file).unwrap().status().unwrap().len(), 4);
+
+    // Changing an ignore file refreshes its directory and those below it.
+    write(&root, "src/.gitignore", "!keep.o\n*.rs\n");
+    backdate(&root.join("src/.gitignore"), false);
+    assert_eq!(ignored(&mut cache, &root), ["build", "build/.gitignore", "build/out", "src/a.o", "src/a.rs"].map(PathBuf::from));
+    assert_eq!(cache.refreshed(), 1);
+
+    write(&root, ".gitignore", "*.md\n");
+    backdate(&root.join(".gitignore"), false);
+    assert_eq!(ignored(&mut cache, &root), ["docs/x.md", "src/a.rs"].map(PathBuf::from));
+    assert_eq!(cache.refreshed(), 4);
+
+    // Adding or removing entries refreshes only their directory.
+    write(&root, "docs/y.md", "");
+    fs::remove_dir_all(root.join("build")).unwrap();
+    backdate(&root.join("docs"), false);
+    backdate(&root, false);
+    assert_eq!(ignored(&mut cache, &root), ["docs/x.md", "docs/y.md", "src/a.rs"].map(PathBuf::from));
+    assert_eq!(cache.refreshed(), 2);
+
+    // Anything else isn't a cache.
+    fs::write(&file, "not a cache").unwrap();
+    let mut cache = StatusCache::load(&root, &file).unwrap();
+    assert_eq!(ignored(&mut cache, &root).len(), 3);
+    assert_eq!(cache.refreshed(), 3);
+}
@@ -0,0 +1,57 @@
+#![allow(dead_code)]
+
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use gitfilter::{Document, Matcher};
+
+/// Compiles the ignore file `text`.
+pub fn matcher(text: &str) -> Matcher {
+    matcher_at(text, "")
+}
+
+/// Compiles the ignore file `text` with patterns relative to `root`.
+pub fn matcher_at(text: &str, root: &str) -> Matcher {
+    Document::parse(text, root).unwrap().to_matcher().unwrap()
+}
+
+/// A directory for one test, empty when created and removed when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Creates the directory for the test `name`, unique to this process.
+    pub fn new(name: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("gitfilter-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Writes `contents` to `file` below `root`, creating its parents.
+pub fn write(root: &Path, file: &str, contents: &str) {
+    let path = root.join(file);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
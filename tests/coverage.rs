@@ -0,0 +1,45 @@
+use gitfilter::{Coverage, Document};
+
+#[test]
+fn test_coverage() {
+    let matcher = Document::parse("*.o\nfoo.o\n!keep.o\n*.swp\nbuild/\n/dead\n", "")
+        .unwrap()
+        .to_matcher()
+        .unwrap();
+
+    let paths = [
+        ("a.o", false), ("foo.o", false), ("x/keep.o", false),
+        ("build", true), ("build", false), ("src/main.rs", false),
+        // Git never matches anything below an excluded directory.
+        ("build/a.swp", false), ("build/b.o", false),
+    ];
+
+    let coverage = Coverage::new(&matcher, paths);
+    let stats: Vec<(String, usize, usize)> = coverage.iter()
+        .map(|c| (c.pattern.to_string(), c.hits, c.decisive))
+        .collect();
+
+    assert_eq!(stats, [
+        ("**/*.o".to_string(), 3, 1),
+        ("**/foo.o".to_string(), 1, 1),
+        ("!**/keep.o".to_string(), 1, 1),
+        ("**/*.swp".to_string(), 0, 0),
+        ("**/build/".to_string(), 1, 1),
+        ("/dead".to_string(), 0, 0),
+    ]);
+
+    let unused: Vec<String> = coverage.unused().map(|p| p.to_string()).collect();
+    assert_eq!(unused, ["**/*.swp", "/dead"]);
+
+    // Patterns are shown as written.
+    let text = coverage.to_string();
+    assert!(text.lines().any(|line| line.ends_with("  *.swp  (unused)")), "{}", text);
+    assert!(!text.contains("**/"), "{}", text);
+
+    let matcher = Document::parse("foo.o\n*.o\n", "").unwrap().to_matcher().unwrap();
+    let coverage = Coverage::new(&matcher, [("foo.o", false), ("a.o", false)]);
+    let overridden: Vec<String> = coverage.overridden().map(|p| p.to_string()).collect();
+    assert_eq!(overridden, ["**/foo.o"]);
+    assert_eq!(coverage.unused().count(), 0);
+    assert!(coverage.to_string().contains("(overridden)"));
+}
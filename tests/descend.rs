@@ -0,0 +1,104 @@
+use gitfilter::{DirState, Matcher, Pattern, PatternSet};
+
+const PATTERNS: &[&str] = &[
+    "*.o", "!keep.o", "target/", "/build", "src/**/gen", "a/**", "**/b/c", "doc/*.md",
+    "*.[rs]", "[!a]*.rs", "{x,y/z}", "x?", "**", "!**/c/", "a/**/*.rs", "/src/*/", "**.txt",
+    "*/c/*", "b/", "!a/b/**", "d\\*", "",
+];
+
+const NAMES: &[&str] = &["a", "b", "c", "src", "x", "y", "z", "keep.o", "f.rs", "gen", "d*"];
+
+/// Every path of up to `depth` components made of `NAMES`, in walk order,
+/// each with the path of its parent directory.
+fn tree(depth: usize) -> Vec<(String, String)> {
+    let mut paths = vec![];
+    let mut frontier = vec![String::new()];
+    for _ in 0..depth {
+        let mut next = vec![];
+        for dir in &frontier {
+            for name in NAMES {
+                let path = match dir.is_empty() {
+                    true => name.to_string(),
+                    false => format!("{}/{}", dir, name),
+                };
+
+                paths.push((dir.clone(), path.clone()));
+                next.push(path);
+            }
+        }
+
+        frontier = next;
+    }
+
+    paths
+}
+
+fn check(matcher: &Matcher, base: &str) {
+    let mut states = std::collections::HashMap::new();
+    states.insert(String::new(), matcher.dir_state(base));
+    for (dir, path) in tree(3) {
+        let full = format!("{}{}", base, path);
+        let name = path.rsplit('/').next().unwrap();
+        let parent: &DirState = &states[&dir];
+        for is_dir in [false, true] {
+            let state = matcher.descend(parent, name, is_dir);
+            assert_eq!(state.is_match(), matcher.matches(&full, is_dir), "{} {:?} ({})", matcher, full, is_dir);
+        }
+
+        let state = matcher.descend(parent, name, true);
+        if state.is_exhausted() {
+            for (_, below) in tree(1) {
+                let below = format!("{}/{}", full, below);
+                assert!(matcher.find_matches(&below, true).next().is_none(), "{} {:?}", matcher, below);
+            }
+        }
+
+        states.insert(path, state);
+    }
+}
+
+#[test]
+fn test_descend_matches() {
+    for pattern in PATTERNS {
+        for root in ["", "/base", "/"] {
+            let matcher = Pattern::new(pattern, root).unwrap().into_matcher().unwrap();
+            let base = match root {
+                "" => String::new(),
+                "/" => "/".into(),
+                root => format!("{}/", root),
+            };
+
+            check(&matcher, &base);
+        }
+    }
+
+    let patterns = PATTERNS.iter().filter(|p| **p != "**").map(|p| Pattern::new(p, "").unwrap());
+    check(&PatternSet::from(patterns).into_matcher().unwrap(), "");
+}
+
+#[test]
+fn test_descend_exhausted() {
+    let matcher = ["/target/", "/src/*.o", "!src/keep.o"].iter()
+        .map(|p| Pattern::new(p, "/repo").unwrap())
+        .collect::<Result<Matcher, _>>()
+        .unwrap();
+
+    let root = matcher.dir_state("/repo");
+    assert!(!root.is_exhausted());
+    assert!(matcher.dir_state("/elsewhere").is_exhausted());
+
+    let target = matcher.descend(&root, "target", true);
+    assert!(target.is_match() && target.is_exhausted());
+    assert!(!matcher.descend(&root, "target", false).is_match());
+    assert!(matcher.descend(&root, "docs", true).is_exhausted());
+
+    let src = matcher.descend(&root, "src", true);
+    assert!(!src.is_match() && !src.is_exhausted());
+    assert!(matcher.descend(&src, "a.o", false).is_match());
+    assert!(!matcher.descend(&src, "keep.o", false).is_match());
+    assert!(matcher.descend(&src, "lib", true).is_exhausted());
+
+    let matcher = Pattern::new("*.log", "/repo").unwrap().into_matcher().unwrap();
+    assert!(matcher.dir_state("/elsewhere").is_exhausted());
+    assert!(!matcher.dir_state("/repo/a/b").is_exhausted());
+}
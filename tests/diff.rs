@@ -0,0 +1,82 @@
+mod common;
+
+use std::path::PathBuf;
+
+use gitfilter::{Diff, Match};
+
+use common::{matcher_at as matcher, write, TempDir};
+
+fn summary(groups: &[gitfilter::Group<'_>]) -> Vec<(String, Vec<String>)> {
+    groups.iter()
+        .map(|group| {
+            let cause = match group.cause {
+                Match::Pattern(p) => p.to_string(),
+                Match::Exception(p) => p.to_string(),
+            };
+
+            let paths = group.paths.iter().map(|(p, _)| p.display().to_string()).collect();
+            (cause, paths)
+        })
+        .collect()
+}
+
+#[test]
+fn test_diff_paths() {
+    let old = matcher("*.o\n!keep.o\nbuild/\n", "");
+    let new = matcher("*.o\n*.a\n!keep.*\n", "");
+    let paths = [
+        ("a.o", false), ("keep.o", false), ("keep.a", false), ("x.a", false),
+        ("y/x.a", false), ("build", true), ("build", false), ("a.c", false),
+    ];
+
+    let diff = Diff::new(&old, &new, paths);
+    assert_eq!(summary(&diff.ignored), [("**/*.a".to_string(), vec!["x.a".to_string(), "y/x.a".into()])]);
+    assert_eq!(summary(&diff.included), [("**/build/".to_string(), vec!["build".to_string()])]);
+
+    let unchanged = matcher("*.o\n!keep.o\nbuild/\n", "");
+    assert!(Diff::new(&old, &unchanged, paths).is_empty());
+
+    let old = matcher("*.log\n", "");
+    let new = matcher("*.log\n!important.log\n", "");
+    let diff = Diff::new(&old, &new, [("a/important.log", false), ("a.log", false)]);
+    assert!(diff.ignored.is_empty());
+    assert!(matches!(diff.included[0].cause, Match::Exception(_)));
+    assert_eq!(summary(&diff.included), [("!**/important.log".to_string(), vec!["a/important.log".into()])]);
+
+    // Nothing below an excluded directory can be re-included.
+    let old = matcher("", "");
+    let new = matcher("build/
+!*.o
+", "");
+    let diff = Diff::new(&old, &new, [("build", true), ("build/x.o", false), ("x.o", false)]);
+    assert_eq!(summary(&diff.ignored), [("**/build/".to_string(), vec!["build".to_string(), "build/x.o".into()])]);
+    assert!(Diff::new(&new, &new, [("build/x.o", false)]).is_empty());
+    let diff = Diff::new(&new, &old, [("build/x.o", false)]);
+    assert_eq!(summary(&diff.included), [("**/build/".to_string(), vec!["build/x.o".to_string()])]);
+}
+
+#[test]
+fn test_diff_tree() {
+    let dir = TempDir::new("diff");
+    for file in ["src/main.rs", "src/main.o", "target/debug/app", "notes.txt", ".git/HEAD"] {
+        write(&dir, file, "");
+    }
+
+    let root = dir.to_str().unwrap();
+    let old = matcher("target/\n*.txt\n", root);
+    let new = matcher("target/**\n*.o\n", root);
+    let diff = Diff::tree(&old, &new, &dir).unwrap();
+
+    let ignored: Vec<PathBuf> = diff.ignored.iter().flat_map(|g| g.paths.iter().map(|p| p.0.clone())).collect();
+    assert_eq!(ignored, [dir.join("src/main.o")]);
+
+    // `target/debug` and its entries were already excluded, with `target`.
+    let included: Vec<_> = diff.included.iter().flat_map(|g| g.paths.iter().cloned()).collect();
+    assert_eq!(included, [(dir.join("notes.txt"), false), (dir.join("target"), true)]);
+    assert_eq!(diff.ignored.len(), 1);
+    assert_eq!(diff.included.len(), 2);
+
+    let text = diff.to_string();
+    assert!(text.contains("+ matched by `*.o`:"), "{}", text);
+    assert!(text.contains("- no longer matched by `target/`:"), "{}", text);
+}
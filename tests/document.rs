@@ -0,0 +1,93 @@
+#[macro_use] mod macros;
+
+use gitfilter::Document;
+
+const GITIGNORE: &str = concat!(
+    "# Build output\n",
+    "/target\n",
+    "*.rlib   \n",
+    "\n",
+    "# Editors\n",
+    ".idea/\n",
+    "*.swp\n",
+    "!keep.swp\n",
+);
+
+#[test]
+fn test_document_roundtrip() {
+    for text in [
+        GITIGNORE,
+        "",
+        "\n\n",
+        "foo",
+        "# just a comment",
+        "a\r\nb\r\n\r\n# c\r\n",
+        "a\nb\r\nc",
+        "trailing\\ \n  \n\t\n",
+    ] {
+        let doc: Document = text.parse().unwrap();
+        assert_eq!(doc.to_string(), text);
+    }
+
+    let doc: Document = GITIGNORE.parse().unwrap();
+    assert_eq!(doc.len(), 8);
+    assert!(doc[0].is_comment());
+    assert!(doc[3].is_blank());
+    assert_eq!(doc[2].as_str(), "*.rlib   ");
+    assert_eq!(doc[2].trimmed(), "*.rlib");
+    assert_eq!(doc.patterns().count(), 5);
+
+    let matcher = doc.to_matcher().unwrap();
+    assert_match!(matcher => "target/");
+    assert_match!(matcher => "a/b.rlib");
+    assert_match!(matcher => "a/.idea/");
+    assert_match!(matcher => "foo.swp");
+    assert_no_match!(matcher => "keep.swp");
+    assert_no_match!(matcher => "a/target/");
+    assert_no_match!(matcher => ".idea");
+
+    // Only spaces are insignificant: a line of tabs is a pattern.
+    let doc: Document = "  \n\t\n \t \n".parse().unwrap();
+    assert!(doc[0].is_blank());
+    assert_eq!(doc.patterns().map(|p| p.to_string()).collect::<Vec<_>>(), ["**/\t", "**/ \t"]);
+    assert_match!(doc.to_matcher().unwrap() => "a/\t");
+}
+
+#[test]
+fn test_document_edits() {
+    let mut doc: Document = GITIGNORE.parse().unwrap();
+    doc.insert(3, "*.o").unwrap();
+    assert_eq!(doc.to_string(), GITIGNORE.replace("*.rlib   \n", "*.rlib   \n*.o\n"));
+
+    let removed = doc.remove(3);
+    assert_eq!(removed.as_str(), "*.o");
+    assert_eq!(doc.to_string(), GITIGNORE);
+
+    let i = doc.position("/target").unwrap();
+    doc.move_line(i, doc.len() - 1);
+    assert!(doc.to_string().ends_with("!keep.swp\n/target\n"));
+    assert!(doc.position("nope").is_none());
+
+    // Appending to a document without a trailing newline keeps it that way.
+    let mut doc: Document = "a\r\nb".parse().unwrap();
+    doc.push("c").unwrap();
+    assert_eq!(doc.to_string(), "a\r\nb\r\nc");
+    doc.remove(2);
+    assert_eq!(doc.to_string(), "a\r\nb");
+    doc.move_line(1, 0);
+    assert_eq!(doc.to_string(), "b\r\na");
+
+    let mut doc: Document = "".parse().unwrap();
+    doc.push("# comment").unwrap().push("foo").unwrap();
+    assert_eq!(doc.to_string(), "# comment\nfoo\n");
+
+    assert!(doc.insert(0, "a/{b").is_err());
+}
+
+#[test]
+fn test_document_dedupe() {
+    let mut doc: Document = "*.o\n# c\nfoo\n!bar\n*.o\nfoo   \n!bar\nfoo/\n".parse().unwrap();
+    assert_eq!(doc.dedupe(), 3);
+    assert_eq!(doc.to_string(), "# c\n*.o\nfoo   \n!bar\nfoo/\n");
+    assert_eq!(doc.dedupe(), 0);
+}
@@ -0,0 +1,108 @@
+use globset::{GlobBuilder, GlobMatcher};
+use gitfilter::{Pattern, PatternSet};
+
+const PATTERNS: &[&str] = &[
+    "foo", "foo.rs", "*.rs", "*.tar.gz", "*~", "*foo.rs", ".rs", "/foo", "/foo.rs", "a/b",
+    "a/b/c.rs", "/a/*.rs", "**/foo", "**/*.rs", "a/**", "a/**/b", "a/**/*.rs", "**", "**/*",
+    "f?o", "[ab].rs", "{foo,bar}", "*.{rs,o}", "foo/", "*.rs/", "*.{gz,tar.gz}", "/a/{b,foo}",
+    "a/{b,x}/c.rs", "x{a,b}", "{foo,f?o}", "{,foo}", "{foo,bar}/", "*.{rs,o}~",
+];
+
+const ROOTS: &[&str] = &["", "/", "/root", "/root/", "root", "root/a"];
+
+const PATHS: &[&str] = &[
+    "", "/", "foo", "/foo", "//foo", "foo/", "a/foo", "/a/foo", "a//foo", "foo.rs", "a/foo.rs",
+    ".rs", "a/.rs", "rs", "x.tar.gz", "a/x.tar.gz", "tar.gz", "x~", "~", "a/b", "/a/b", "a/b/c.rs",
+    "a/b/x/c.rs", "a/x.rs", "a/x/y.rs", "a/b/b", "a/x/b", "fao", "a.rs", "c.rs", "bar", "x.o",
+    "afoo.rs", "root", "root/foo", "root/a/foo", "root/x.rs", "root/a/b", "root/a", "root/a/b/c.rs",
+    "/root", "/root/foo", "/root/a/foo", "/root/x.rs", "/root/a/b", "/root/.rs", "/root//foo",
+    "rootfoo", "/rootfoo", "/rootx.rs", "root/a/x.tar.gz", "/root/a/x/b", "root/a/a/b",
+];
+
+fn globs(patterns: &[Pattern]) -> Vec<GlobMatcher> {
+    patterns.iter()
+        .map(|p| GlobBuilder::new(p.glob()).literal_separator(true).build().unwrap().compile_matcher())
+        .collect()
+}
+
+#[test]
+fn test_engine_matches_globs() {
+    for root in ROOTS {
+        let patterns: Vec<Pattern> = PATTERNS.iter().map(|p| Pattern::new(p, root).unwrap()).collect();
+        for pattern in &patterns {
+            let glob = GlobBuilder::new(pattern.glob()).literal_separator(true).build().unwrap();
+            let glob = glob.compile_matcher();
+            let matcher = pattern.clone().into_matcher().unwrap();
+            for path in PATHS {
+                assert_eq!(
+                    matcher.find_matches(path, true).count() == 1,
+                    glob.is_match(path),
+                    "pattern {:?} (glob {:?}) on {:?}", pattern.to_string(), pattern.glob(), path
+                );
+            }
+        }
+
+        // The same patterns, all at once: indices must be reported in order.
+        let globs = globs(&patterns);
+        let matcher = PatternSet::from(patterns.clone()).into_matcher().unwrap();
+        for path in PATHS {
+            let expected: Vec<String> = patterns.iter()
+                .zip(&globs)
+                .filter(|(_, glob)| glob.is_match(path))
+                .map(|(p, _)| p.to_string())
+                .collect();
+
+            let actual: Vec<String> = matcher.find_matches(path, true)
+                .map(|m| m.pattern().to_string())
+                .collect();
+
+            assert_eq!(actual, expected, "root {:?}, path {:?}", root, path);
+        }
+    }
+}
+
+#[test]
+fn test_root_index_matches_globs() {
+    let mut patterns: Vec<Pattern> = vec![];
+    for root in ROOTS.iter().chain(&["/root/a", "/r[o]ot", "/rootx"]) {
+        patterns.extend(PATTERNS.iter().map(|p| Pattern::new(p, root).unwrap()));
+    }
+
+    // These escape their root, so can match paths outside of it.
+    patterns.push(Pattern::new("../x.rs", "/root/a").unwrap().rootful_dedotted());
+    patterns.push(Pattern::new("../../foo", "root/a").unwrap().rootful_dedotted());
+
+    let mut paths: Vec<String> = PATHS.iter().map(|p| p.to_string()).collect();
+    paths.extend(["/root/x.rs", "/root/a/x.rs", "foo", "/root/a", "/root/a/", "/rootx/foo", "/r[o]ot/foo"].map(Into::into));
+    paths.extend(PATHS.iter().map(|p| format!("/root/a/{}", p)));
+    paths.extend(PATHS.iter().map(|p| format!("/root/{}", p)));
+
+    let globs = globs(&patterns);
+    let matcher = PatternSet::from(patterns).into_matcher().unwrap();
+    for path in &paths {
+        let expected: Vec<usize> = globs.iter()
+            .enumerate()
+            .filter(|(_, glob)| glob.is_match(path))
+            .map(|(i, _)| i)
+            .collect();
+
+        let actual: Vec<usize> = matcher.find_matches(path, true)
+            .map(|m| matcher.iter().position(|p| std::ptr::eq(p, m.pattern())).unwrap())
+            .collect();
+
+        assert_eq!(actual, expected, "path {:?}", path);
+    }
+}
+
+#[test]
+fn test_root_index_sibling_prefix() {
+    // The glob starts with the root's text but climbs into a sibling of it.
+    let pattern = Pattern::new("../a2/x", "/root/a").unwrap().rootful_dedotted();
+    assert_eq!(pattern.glob(), "/root/a2/x");
+
+    let matcher = PatternSet::from([pattern, Pattern::new("y", "/root/a").unwrap()]).into_matcher().unwrap();
+    assert!(matcher.matches("/root/a2/x", false));
+    assert!(matcher.matches("/root/a/y", false));
+    assert!(!matcher.matches("/root/a/x", false));
+    assert!(!matcher.matches("/root/a2/y", false));
+}
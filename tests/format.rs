@@ -0,0 +1,66 @@
+use gitfilter::{Formatter, Pattern};
+
+#[test]
+fn test_format() {
+    let fmt = Formatter::new();
+    for (input, expected) in [
+        ("", ""),
+        ("foo", "foo\n"),
+        ("foo   \n  \n# comment  \n", "foo\n\n# comment\n"),
+        ("trailing\\ \n", "trailing\\ \n"),
+        ("**/foo\n/**/bar/\n!**/baz\n", "foo\nbar/\n!baz\n"),
+        ("**/**/a/b\n**/a/b\n", "**/a/b\n"),
+        ("**/#x\n**/!y\n/foo\n", "**/#x\n**/!y\n/foo\n"),
+        ("*.o\nfoo\n*.o\n", "foo\n*.o\n"),
+        ("a\r\nb  \r\n", "a\r\nb\r\n"),
+    ] {
+        assert_eq!(fmt.format_str(input).unwrap(), expected, "formatting {:?}", input);
+        assert_eq!(fmt.format_str(expected).unwrap(), expected, "idempotence {:?}", expected);
+    }
+}
+
+#[test]
+fn test_format_sort() {
+    let fmt = Formatter::new().sort(true);
+    for (input, expected) in [
+        ("c\nb\na\n", "a\nb\nc\n"),
+        ("# z\nc\nb\n\n# y\nz\ny\n", "# z\nb\nc\n\n# y\ny\nz\n"),
+        ("c\nb\n!c\n!a\nz\ny\n", "b\nc\n!a\n!c\ny\nz\n"),
+        ("*.o\n!keep.o\nbuild/\n*.a\n", "*.o\n!keep.o\n*.a\nbuild/\n"),
+    ] {
+        assert_eq!(fmt.format_str(input).unwrap(), expected, "formatting {:?}", input);
+    }
+}
+
+#[test]
+fn test_format_pattern() {
+    let fmt = Formatter::new();
+    for root in ["", "/root", "/root/", "root/a"] {
+        for (input, expected) in [
+            ("foo", "foo"),
+            ("**/foo/", "foo/"),
+            ("**/**/foo", "foo"),
+            ("/**/foo", "foo"),
+            ("!**/*.o", "!*.o"),
+            ("/target", "/target"),
+            ("a/b/", "a/b/"),
+            ("/a/b", "a/b"),
+            ("**/a/b", "**/a/b"),
+            ("/**", "/**"),
+            ("**", "**"),
+            ("a/**/", "a/**/"),
+            ("/#x", "/#x"),
+            ("**/!x", "**/!x"),
+            ("\\#x", "\\#x"),
+            ("trailing\\ ", "trailing\\ "),
+            ("/", "/"),
+        ] {
+            let pattern = Pattern::new(input, root).unwrap();
+            let line = fmt.format_pattern(&pattern);
+            assert_eq!(line, expected, "{:?} in {:?}", input, root);
+
+            let parsed = Pattern::new(&line, root).unwrap();
+            assert_eq!(fmt.format_pattern(&parsed), line, "{:?} in {:?}", input, root);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use std::cell::Cell;
+use std::fs;
+
+use gitfilter::Document;
+
+use common::{matcher, TempDir};
+
+#[test]
+fn test_matches_with() {
+    let matcher = matcher("*.o\nbuild/\n!keep.o\nout/\n!out\n/logs/\n*.log\n");
+    let paths = ["a.o", "keep.o", "build", "x/build", "out", "logs", "logs/a.log", "x.rs", "build.o"];
+    for path in paths {
+        for is_dir in [false, true] {
+            let calls = Cell::new(0);
+            let lazily = matcher.matches_with(path, || { calls.set(calls.get() + 1); is_dir });
+            assert_eq!(lazily, matcher.matches(path, is_dir), "{}", path);
+            assert!(calls.get() <= 1);
+
+            // Directory-ness is only needed when a `dir_only` pattern decides.
+            let decisive = matcher.find_match(path, true).is_some_and(|m| m.pattern().to_string().ends_with('/'));
+            assert_eq!(calls.get() == 1, decisive, "{}", path);
+        }
+    }
+
+    let never = || -> bool { panic!("directory-ness isn't needed") };
+    assert!(matcher.matches_with("a.o", never));
+    assert!(!matcher.matches_with("x.rs", never));
+    assert!(!matcher.matches_with("out", never));
+    assert!(matcher.matches_with("logs", || true));
+}
+
+#[test]
+fn test_matches_fs() {
+    let root = TempDir::new("lazy");
+    fs::create_dir_all(root.join("build")).unwrap();
+    fs::write(root.join("cache"), "").unwrap();
+
+    let matcher = Document::parse("build/\ncache/\n*.o\n", &root).unwrap().to_matcher().unwrap();
+    assert!(matcher.matches_fs(root.join("build")));
+    assert!(!matcher.matches_fs(root.join("cache")));
+    assert!(!matcher.matches_fs(root.join("missing")));
+    assert!(matcher.matches_fs(root.join("missing.o")));
+}
@@ -0,0 +1,76 @@
+use gitfilter::{Document, Error, Limit, Limits, Pattern, PatternSet};
+
+fn exceeded<T: std::fmt::Debug>(result: Result<T, Error>) -> (Limit, usize) {
+    match result {
+        Err(Error::LimitExceeded { limit, max }) => (limit, max),
+        result => panic!("limit not exceeded: {:?}", result),
+    }
+}
+
+#[test]
+fn test_parse_limits() {
+    let limits = Limits::new().max_patterns(3).max_pattern_len(8);
+    let document = Document::parse_limited("a\n# comment\n\nb\n!c\n", "", limits).unwrap();
+    assert_eq!(document.limits(), &limits);
+    assert_eq!(exceeded(Document::parse_limited("a\nb\nc\nd\n", "", limits)), (Limit::Patterns, 3));
+    assert_eq!(exceeded(Document::parse_limited("123456789\n", "", limits)), (Limit::PatternLength, 8));
+    assert!(Document::parse_limited("12345678\n# a much longer comment\n", "", limits).is_ok());
+
+    // Parsing stops at the first line over the limit.
+    let huge = "x\n".repeat(100_000) + "[";
+    assert_eq!(exceeded(Document::parse_limited(&huge, "", limits)), (Limit::Patterns, 3));
+    assert!(matches!(Document::parse(&huge, ""), Err(Error::Glob(_))));
+
+    let mut document = document;
+    assert_eq!(exceeded(document.push("d")), (Limit::Patterns, 3));
+    assert!(document.push("# comment").is_ok());
+    document.remove(0);
+    assert!(document.push("d").is_ok());
+    assert_eq!(exceeded(document.push("e")), (Limit::Patterns, 3));
+
+    let error = Document::parse_limited("a\nb\nc\nd\n", "", limits).unwrap_err();
+    assert_eq!(error.to_string(), "exceeded the limit of 3 patterns");
+
+    let sandboxed = Limits::new().sandboxed(true);
+    assert!(matches!(Document::parse_limited("../x", "/r", sandboxed), Err(Error::EscapesRoot { .. })));
+    assert!(Document::parse_limited("../x", "/r", limits).is_ok());
+}
+
+#[test]
+fn test_compiled_size_limit() {
+    let alternation = format!("{{{}}}", (0..1000).map(|i| format!("n{}", i)).collect::<Vec<_>>().join(","));
+    let text = format!("*.o\n{}\n", alternation);
+    let limits = Limits::new().max_compiled_size(4096);
+
+    // The pattern is short enough to parse, but too costly to compile.
+    let document = Document::parse_limited(&text, "", limits).unwrap();
+    assert_eq!(exceeded(document.to_matcher()), (Limit::CompiledSize, 4096));
+    assert!(Document::parse(&text, "").unwrap().to_matcher().is_ok());
+
+    let mut set = PatternSet::from(["*.o", "build/"].map(|p| p.parse::<Pattern>().unwrap()));
+    set.limit(limits.max_patterns(2));
+    let mut matcher = set.into_matcher().unwrap();
+    assert_eq!(exceeded(matcher.push("x".parse().unwrap())), (Limit::Patterns, 2));
+    assert_eq!(matcher.len(), 2);
+
+    let id = matcher.id(0).unwrap();
+    let costly = Pattern::new(&alternation, "").unwrap();
+    assert_eq!(exceeded(matcher.replace(id, costly.clone())), (Limit::CompiledSize, 4096));
+    assert!(matcher.matches("a.o", false));
+    assert!(matcher.replace(id, "*.a".parse().unwrap()).unwrap().is_some());
+
+    // Only what's currently in the matcher counts towards the limits.
+    for _ in 0..1000 {
+        assert!(matcher.replace(id, "*.a".parse().unwrap()).unwrap().is_some());
+    }
+
+    let build = matcher.id(1).unwrap();
+    matcher.remove(build).unwrap();
+    matcher.push("x".parse().unwrap()).unwrap();
+    assert_eq!(exceeded(matcher.push("y".parse().unwrap())), (Limit::Patterns, 2));
+
+    // The limits survive a round trip through a pattern set.
+    let mut set = matcher.into_pattern_set();
+    set.add(costly);
+    assert_eq!(exceeded(set.into_matcher()), (Limit::Patterns, 2));
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use std::path::Path;
+
+use gitfilter::{CachedMatcher, Matcher};
+
+use common::matcher;
+
+/// Applies the parent-exclusion rule directly, without a memo.
+fn excluded(matcher: &Matcher, path: &str, is_dir: bool) -> bool {
+    let path = Path::new(path);
+    path.ancestors().skip(1).take_while(|a| a.file_name().is_some()).any(|a| matcher.matches(a, true))
+        || matcher.matches(path, is_dir)
+}
+
+fn paths() -> Vec<String> {
+    let names = ["target", "keep", "x.log", "important.log", "sub"];
+    let mut paths = vec![];
+    for a in names {
+        paths.push(a.to_string());
+        for b in names {
+            paths.push(format!("{}/{}", a, b));
+            for c in names {
+                paths.push(format!("/r/{}/{}/{}", a, b, c));
+            }
+        }
+    }
+
+    paths
+}
+
+#[test]
+fn test_cached_matcher() {
+    let texts = ["target/\n!target/keep", "*.log\n!important.log", "sub/*\n!sub/keep/", "/r/keep/"];
+    for text in texts {
+        for capacity in [0, 1, 3, 1000] {
+            let cached = CachedMatcher::new(matcher(text), capacity);
+            for round in 0..2 {
+                for path in paths() {
+                    for is_dir in [false, true] {
+                        let expected = excluded(cached.matcher(), &path, is_dir);
+                        assert_eq!(cached.matches(&path, is_dir), expected, "{:?}: {} ({})", text, path, round);
+                    }
+                }
+            }
+
+            assert_eq!(cached.hits() > 0, capacity > 0);
+        }
+    }
+
+    // Exceptions can't re-include anything below an excluded directory.
+    let cached = CachedMatcher::new(matcher("target/\n!target/keep"), 16);
+    assert!(!cached.matcher().matches("target/keep", false));
+    assert!(cached.matches("target/keep", false));
+}
+
+#[test]
+fn test_cached_matcher_counters() {
+    let cached = CachedMatcher::new(matcher("build/"), 2);
+    assert!(!cached.matches("a/b/c.rs", false));
+    assert_eq!((cached.hits(), cached.misses()), (0, 2));
+
+    assert!(!cached.matches("a/b/d.rs", false));
+    assert_eq!((cached.hits(), cached.misses()), (1, 2));
+
+    // `a` is found, but `a/x` and `a/x/build` then evict both `a/b` and `a`.
+    assert!(cached.matches("a/x/build/y", false));
+    assert_eq!((cached.hits(), cached.misses()), (2, 4));
+    assert!(!cached.matches("a/b/e.rs", false));
+    assert_eq!((cached.hits(), cached.misses()), (2, 6));
+
+    cached.clear();
+    assert_eq!((cached.hits(), cached.misses()), (0, 0));
+}
+
+#[test]
+fn test_cached_matcher_threads() {
+    fn assert_sync<T: Sync + Send>() {}
+    assert_sync::<CachedMatcher>();
+
+    let cached = CachedMatcher::new(matcher("target/\n*.log\n!important.log"), 64);
+    let paths = paths();
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for path in &paths {
+                    assert_eq!(cached.matches(path, false), excluded(cached.matcher(), path, false));
+                }
+            });
+        }
+    });
+
+    let queries = 4 * paths.len() as u64;
+    assert!(cached.hits() + cached.misses() >= queries - 4 * 5);
+}
@@ -0,0 +1,64 @@
+use gitfilter::{Pattern, PatternSet};
+
+fn set(root: &str, patterns: &[&str]) -> PatternSet {
+    patterns.iter().map(|p| Pattern::new(p, root).unwrap()).collect()
+}
+
+fn assert_minimized(root: &str, patterns: &[&str], expected: &[&str], paths: &[&str]) {
+    let mut minimized = set(root, patterns);
+    minimized.minimize().unwrap();
+
+    let actual: Vec<String> = minimized.iter().map(|p| p.to_string()).collect();
+    let expected: Vec<String> = set(root, expected).iter().map(|p| p.to_string()).collect();
+    assert_eq!(actual, expected);
+
+    let original = set(root, patterns).into_matcher().unwrap();
+    let minimized = minimized.into_matcher().unwrap();
+    for path in paths {
+        for is_dir in [false, true] {
+            assert_eq!(
+                original.matches(path, is_dir),
+                minimized.matches(path, is_dir),
+                "{} and {} disagree on {:?} (is_dir = {})", original, minimized, path, is_dir
+            );
+        }
+    }
+}
+
+#[test]
+fn test_minimize() {
+    let paths = &[
+        "a.o", "a.obj", "a.c", "x/a.o", "x/a.obj", "keep.o", "x/keep.o", "build",
+        "x/build", "foo", "bar", "x/foo", "target", "target/x", "target/a.o", "logs/a.log",
+        "logs/x/a.log", "a.log", "/abs/a.o", "/abs/foo", "foo.o",
+    ];
+
+    assert_minimized("", &["*.o", "*.obj"], &["*.{o,obj}"], paths);
+    assert_minimized("", &["*.o", "foo", "*.obj", "bar"], &["*.{o,obj}", "{foo,bar}"], paths);
+    assert_minimized("", &["*.o", "!keep.o", "*.obj"], &["*.o", "!keep.o", "*.obj"], paths);
+    assert_minimized("", &["build/", "*.o/", "*.a/"], &["build/", "*.{o,a}/"], paths);
+    assert_minimized("", &["*.o", "*.obj/"], &["*.o", "*.obj/"], paths);
+
+    // Duplicates: only the last copy can ever decide.
+    assert_minimized("", &["foo", "!foo", "foo"], &["foo"], paths);
+    assert_minimized("", &["foo", "*.o", "foo"], &["*.o", "foo"], paths);
+
+    // Leading exceptions never change the outcome.
+    assert_minimized("", &["!foo", "!bar", "foo", "!foo.o"], &["foo", "!foo.o"], paths);
+
+    // Patterns subsumed by later ones.
+    assert_minimized("", &["foo.o", "x/*.o", "*.o"], &["*.o"], paths);
+    assert_minimized("", &["/target/a.o", "target/**"], &["target/**"], paths);
+    assert_minimized("", &["/target/a.o", "!target/**", "x"], &["x"], paths);
+    assert_minimized("", &["x/foo", "foo/", "foo"], &["foo"], paths);
+    assert_minimized("", &["foo", "foo/"], &["foo", "foo/"], paths);
+    assert_minimized("", &["logs/**/*.log", "*.log"], &["*.log"], paths);
+    assert_minimized("", &["*.log", "logs/**/*.log"], &["*.log", "logs/**/*.log"], paths);
+
+    // Roots are kept apart.
+    assert_minimized("/abs", &["*.o", "*.obj", "foo", "bar"], &["*.{o,obj}", "{foo,bar}"], paths);
+    let mut mixed = set("/abs", &["*.o"]);
+    mixed.extend(set("/other", &["*.obj"]).into_iter());
+    mixed.minimize().unwrap();
+    assert_eq!(mixed.len(), 2);
+}
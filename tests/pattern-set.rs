@@ -164,3 +164,48 @@ fn test_pattern_set_matches() {
         ]
     );
 }
+
+#[test]
+fn test_pattern_set_ids() {
+    use gitfilter::{Pattern, PatternSet};
+
+    let pattern = |s: &str| s.parse::<Pattern>().unwrap();
+    let mut set = PatternSet::new();
+    set.add(pattern("*.o")).add(pattern("*.obj"));
+    let keep = set.push(pattern("!keep.o"));
+    let (o, obj) = (set.id(0).unwrap(), set.id(1).unwrap());
+    assert_eq!(set.position(keep), Some(2));
+
+    let log = set.insert_at(0, pattern("*.log"));
+    assert_eq!(set.position(log), Some(0));
+    assert_eq!(set.position(keep), Some(3));
+    assert_eq!(set.get(obj).unwrap().to_string(), "**/*.obj");
+
+    set.retain(|p| !p.to_string().ends_with(".log"));
+    assert_eq!(set.position(log), None);
+    assert_eq!(set.position(o), Some(0));
+
+    // The merged pattern takes the place and ID of the first it replaces.
+    set.minimize().unwrap();
+    assert_eq!(set.get(o).unwrap().to_string(), "**/*.{o,obj}");
+    assert_eq!(set.get(obj).map(|p| p.to_string()), None);
+    assert_eq!(set.position(keep), Some(1));
+
+    // IDs survive compilation, edits and decompilation.
+    let mut matcher = set.into_matcher().unwrap();
+    assert!(!matcher.matches("keep.o", false));
+    assert_eq!(matcher.position(keep), Some(1));
+    let a = matcher.push(pattern("*.a")).unwrap();
+    assert!(a != o && a != keep && a != log);
+
+    let mut set = matcher.into_pattern_set();
+    assert_eq!(set.position(a), Some(2));
+    assert_eq!(set.remove(keep).unwrap().to_string(), "!**/keep.o");
+    assert!(set.remove(keep).is_none());
+
+    let b = set.push(pattern("*.b"));
+    assert!(b != a && b != keep);
+    let matcher = set.into_matcher().unwrap();
+    assert!(matcher.matches("keep.o", false));
+    assert_eq!(matcher.to_string(), "[**/*.{o,obj}, **/*.a, **/*.b]");
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use common::matcher;
+
+const NAMES: &[&str] = &["target", "keep", "x.log", "important.log", "sub", "build", "a.keep"];
+
+/// Every path of one or two components below `dir` made of `NAMES`.
+fn below(dir: &str) -> Vec<String> {
+    let mut paths = vec![];
+    for a in NAMES {
+        paths.push(format!("{}/{}", dir, a));
+        for b in NAMES {
+            paths.push(format!("{}/{}/{}", dir, a, b));
+        }
+    }
+
+    paths
+}
+
+#[test]
+fn test_descendants_sound() {
+    let texts = [
+        "target/**", "/target/**\n!target/keep", "*.log\n!important.log", "build/", "**\n!x.log",
+        "**/*\n!**/*.keep", "target/**\n!/target/keep/", "sub/**\n*.log\n!sub/**/*.log", "",
+        "target/**/*\n!**/important.log\n**/important.log",
+    ];
+
+    for text in texts {
+        let matcher = matcher(text);
+        for dir in ["target", "target/sub", "sub", "build", "a/b"] {
+            let paths = below(dir);
+            let outcomes = || paths.iter().flat_map(|p| [matcher.matches(p, false), matcher.matches(p, true)]);
+            if !matcher.may_include_descendants(dir) {
+                assert!(outcomes().all(|m| m), "{:?} in {}: something included", text, dir);
+            }
+
+            if !matcher.may_ignore_descendants(dir) {
+                assert!(outcomes().all(|m| !m), "{:?} in {}: something ignored", text, dir);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_descendants_prune() {
+    let m = matcher("target/**\n");
+    assert!(!m.may_include_descendants("target"));
+    assert!(m.may_ignore_descendants("target"));
+    assert!(!m.may_ignore_descendants("src"));
+
+    let m = matcher("target/**\n!target/keep\n");
+    assert!(m.may_include_descendants("target"));
+    assert!(!m.may_include_descendants("target/sub"));
+
+    let m = matcher("/vendor/**\n!*.keep\n");
+    assert!(m.may_include_descendants("vendor"));
+
+    let m = matcher("**/*\n");
+    assert!(!m.may_include_descendants("anything/at/all"));
+
+    // A directory-only pattern leaves files below it alone.
+    let m = matcher("target/**/\n");
+    assert!(m.may_include_descendants("target"));
+
+    // Exceptions alone never ignore anything.
+    let m = matcher("!*.rs\n");
+    assert!(!m.may_ignore_descendants("src"));
+    assert!(m.may_include_descendants("src"));
+
+    let m = matcher("");
+    assert!(!m.may_ignore_descendants("src"));
+    assert!(m.may_include_descendants("src"));
+}
@@ -0,0 +1,96 @@
+use gitfilter::{Document, Error, Pattern};
+
+const ESCAPING: &[&str] = &[
+    "../x.rs", "/../x", "a/../../x", "**/../x", "a/**/../../x", "!../secret/", "{..}/x", "[.][.]/x",
+    ".?/x", "a/{..,b}/{..}/x", "{a/..,..}/x",
+];
+
+const CONTAINED: &[&str] = &[
+    "x.rs", "a/../x.rs", "./a/./b", "a/b/../../c", "*/..", "/target/", "a/{..}/x", ".*", "*.{o,a}",
+    "*/x", "x?y",
+];
+
+#[test]
+fn test_escapes_root() {
+    for root in ["", "/root/a", "root/a", "/root/a/"] {
+        for text in ESCAPING {
+            assert!(Pattern::new(text, root).unwrap().escapes_root(), "{:?} in {:?}", text, root);
+        }
+
+        for text in CONTAINED {
+            assert!(!Pattern::new(text, root).unwrap().escapes_root(), "{:?} in {:?}", text, root);
+        }
+    }
+
+    // Once dedotted with its root, an escaping pattern is outside of it.
+    let escaped = Pattern::new("../x.rs", "/root/a").unwrap().rootful_dedotted();
+    assert_eq!(escaped.glob(), "/root/x.rs");
+    assert!(escaped.escapes_root());
+    assert!(!Pattern::new("a/../x.rs", "/root/a").unwrap().rootful_dedotted().escapes_root());
+}
+
+#[test]
+fn test_dedotted() {
+    let dedotted = Pattern::new("a/b/../../c/./d", "/root").unwrap().dedotted();
+    assert_eq!(dedotted.glob(), "/root/c/d");
+    let checked = Pattern::new("a/b/../../c/./d", "/root").unwrap().checked_dedotted().unwrap();
+    assert_eq!(checked.glob(), dedotted.glob());
+
+    // Climbing above the root is clamped, unless checked.
+    let escaping = Pattern::new("a/../../x.rs", "/root").unwrap();
+    assert_eq!(escaping.clone().dedotted().glob(), "/root/x.rs");
+    match escaping.checked_dedotted() {
+        Err(Error::EscapesRoot { pattern, root }) => {
+            assert_eq!((pattern.as_str(), root.as_str()), ("/root/a/../../x.rs", "/root"));
+        }
+        result => panic!("{:?}", result),
+    }
+}
+
+#[test]
+fn test_sandboxed_document() {
+    let text = CONTAINED.join("\n");
+    let document = Document::parse_sandboxed(&text, "/root/a").unwrap();
+    assert!(document.is_sandboxed());
+    assert_eq!(document.patterns().count(), CONTAINED.len());
+
+    for escaping in ESCAPING {
+        let text = format!("*.o\n# comment\n{}\n", escaping);
+        assert!(Document::parse(&text, "/root/a").is_ok());
+        let error = Document::parse_sandboxed(&text, "/root/a").unwrap_err();
+        assert_eq!(error.to_string(), format!("pattern `{}` escapes its root `/root/a`", escaping));
+    }
+
+    // Lines added later are held to the same standard.
+    let mut document = Document::parse_sandboxed("*.o\n", "").unwrap();
+    assert!(matches!(document.push("../x"), Err(Error::EscapesRoot { .. })));
+    assert!(document.push("x/../y").is_ok());
+    assert_eq!(document.to_string(), "*.o\nx/../y\n");
+    assert!(!Document::parse("", "").unwrap().is_sandboxed());
+}
+
+#[test]
+fn test_sandboxed_matcher() {
+    // Patterns added to a sandboxed set or matcher are held to it too.
+    let document = Document::parse_sandboxed("*.o\n", "/r").unwrap();
+    let mut set = document.to_pattern_set();
+    set.push(Pattern::new("../etc/x", "/r").unwrap());
+    assert!(matches!(set.into_matcher(), Err(Error::EscapesRoot { .. })));
+
+    let mut matcher = document.to_matcher().unwrap();
+    for escaping in ESCAPING {
+        let error = matcher.push(Pattern::new(escaping, "/r").unwrap()).unwrap_err();
+        assert!(matches!(error, Error::EscapesRoot { .. }));
+    }
+
+    let id = matcher.id(0).unwrap();
+    let error = matcher.replace(id, Pattern::new("../etc/x", "/r").unwrap()).unwrap_err();
+    assert!(matches!(error, Error::EscapesRoot { .. }));
+    assert!(!matcher.matches("/r/../etc/x", false));
+    assert!(matcher.matches("/r/a.o", false));
+    assert_eq!(matcher.len(), 1);
+
+    for contained in CONTAINED {
+        matcher.push(Pattern::new(contained, "/r").unwrap()).unwrap();
+    }
+}
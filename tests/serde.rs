@@ -0,0 +1,122 @@
+#![cfg(feature = "serde")]
+
+use gitfilter::{Document, Error, Limits, Matcher, Pattern, PatternSet};
+
+fn set() -> PatternSet {
+    let mut set = Document::parse("*.o\n!keep.o\n/build/\n", "/root/project").unwrap().to_pattern_set();
+    set.add(Pattern::new("../x.rs", "/root/project/a").unwrap().rootful_dedotted());
+    set.add("target/".parse().unwrap());
+    set
+}
+
+fn describe(patterns: &[Pattern]) -> Vec<(String, String, String)> {
+    patterns.iter().map(|p| (p.to_string(), p.glob().to_string(), p.root().to_string())).collect()
+}
+
+#[test]
+fn test_pattern_set_round_trip() {
+    let mut set = set();
+    let removed = set.id(1).unwrap();
+    set.remove(removed);
+
+    let json = serde_json::to_string(&set).unwrap();
+    let mut restored: PatternSet = serde_json::from_str(&json).unwrap();
+    assert_eq!(describe(&restored), describe(&set));
+    let ids = |set: &PatternSet| (0..4).map(|i| set.id(i)).collect::<Vec<_>>();
+    assert_eq!(ids(&restored), ids(&set));
+    assert_eq!(restored.get(removed).map(|p| p.to_string()), None);
+
+    // IDs keep being handed out where they left off.
+    let (a, b) = (set.push("a".parse().unwrap()), restored.push("a".parse().unwrap()));
+    assert_eq!(a, b);
+    assert_eq!(serde_json::to_string(&set).unwrap(), serde_json::to_string(&restored).unwrap());
+}
+
+#[test]
+fn test_matcher_round_trip() {
+    let mut set = set();
+    set.limit(Limits::new().max_patterns(10));
+    let mut matcher = set.into_matcher().unwrap();
+    let id = matcher.id(0).unwrap();
+    matcher.replace(id, Pattern::new("*.a", "/root/project").unwrap()).unwrap();
+
+    let json = serde_json::to_string_pretty(&matcher).unwrap();
+    let restored: Matcher = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.to_string(), matcher.to_string());
+    assert_eq!(restored.id(0), Some(id));
+
+    let paths = [
+        "/root/project/x.a", "/root/project/keep.o", "/root/project/build",
+        "/root/project/x.rs", "/root/x.rs", "a/target",
+    ];
+    for path in paths {
+        for is_dir in [false, true] {
+            assert_eq!(restored.matches(path, is_dir), matcher.matches(path, is_dir), "{}", path);
+        }
+    }
+
+    let set: PatternSet = serde_json::from_str(&json).unwrap();
+    let mut restored = set.into_matcher().unwrap();
+    for _ in 0..5 {
+        restored.push("x".parse().unwrap()).unwrap();
+    }
+
+    // The limits come along with the patterns.
+    assert!(restored.push("x".parse().unwrap()).is_err());
+}
+
+#[test]
+fn test_format() {
+    let set = PatternSet::from([Pattern::new("!*.o/", "/r").unwrap()]);
+    let json = serde_json::to_value(&set).unwrap();
+    let expected = serde_json::json!({
+        "version": 1,
+        "next_id": 1,
+        "precompose": false,
+        "limits": { "patterns": null, "pattern_len": null, "compiled_size": null, "sandboxed": false },
+        "patterns": [
+            { "id": 0, "glob": "/r/**/*.o", "root": "/r", "exception": true, "dir_only": true, "rooted": false }
+        ],
+    });
+
+    assert_eq!(json, expected);
+
+    // Only the version, IDs, globs and roots are required.
+    let minimal = r#"{ "version": 1, "next_id": 2, "patterns": [{ "id": 1, "glob": "**/*.o", "root": "" }] }"#;
+    let matcher: Matcher = serde_json::from_str(minimal).unwrap();
+    assert!(matcher.matches("a/b.o", false));
+
+    let unsupported = r#"{ "version": 2, "next_id": 0, "patterns": [] }"#;
+    let error = serde_json::from_str::<PatternSet>(unsupported).unwrap_err();
+    assert!(error.to_string().contains("unsupported pattern set version 2"));
+
+    let duplicate = r#"{ "version": 1, "next_id": 2, "patterns": [
+        { "id": 1, "glob": "a", "root": "" }, { "id": 1, "glob": "b", "root": "" }
+    ] }"#;
+    assert!(serde_json::from_str::<PatternSet>(duplicate).is_err());
+
+    let invalid = r#"{ "version": 1, "next_id": 1, "patterns": [{ "id": 0, "glob": "[", "root": "" }] }"#;
+    assert!(serde_json::from_str::<PatternSet>(invalid).is_err());
+
+    // A pattern on its own is still a string.
+    let pattern: Pattern = serde_json::from_str(r#""!keep.o""#).unwrap();
+    assert_eq!(serde_json::to_string(&pattern).unwrap(), r#""!**/keep.o""#);
+}
+
+#[test]
+fn test_sandboxed() {
+    // As when built in memory, sandboxed sets may hold escaping patterns but
+    // don't compile, so a matcher that compiled reloads as it was.
+    let escaping = r#"{ "version": 1, "next_id": 1, "limits": { "sandboxed": true },
+        "patterns": [{ "id": 0, "glob": "/r/../x", "root": "/r" }] }"#;
+    let set = serde_json::from_str::<PatternSet>(escaping).unwrap();
+    assert!(matches!(set.into_matcher(), Err(Error::EscapesRoot { .. })));
+    let error = serde_json::from_str::<Matcher>(escaping).unwrap_err();
+    assert!(error.to_string().contains("pattern `/r/../x` escapes its root `/r`"));
+    assert!(serde_json::from_str::<Matcher>(&escaping.replace("true", "false")).is_ok());
+
+    let contained = escaping.replace("/r/../x", "/r/a/../x");
+    let matcher = serde_json::from_str::<Matcher>(&contained).unwrap();
+    let json = serde_json::to_string(&matcher).unwrap();
+    assert_eq!(serde_json::to_string(&serde_json::from_str::<Matcher>(&json).unwrap()).unwrap(), json);
+}
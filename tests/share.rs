@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+use gitfilter::{Document, Matcher, Pattern, PatternSet};
+
+fn matcher() -> Matcher {
+    Document::parse("*.o\n!keep.o\nbuild/\n/target\n", "/root/project").unwrap().to_matcher().unwrap()
+}
+
+#[test]
+fn test_cheap_clones() {
+    let matcher = matcher();
+    let clone = matcher.clone();
+    assert!(std::ptr::eq(&matcher[0], &clone[0]));
+
+    // Editing a clone copies what it shares, leaving the original unchanged.
+    let mut edited = clone.clone();
+    edited.push(Pattern::new("*.rs", "/root/project").unwrap()).unwrap();
+    assert!(!std::ptr::eq(&matcher[0], &edited[0]));
+    assert!(std::ptr::eq(&matcher[0], &clone[0]));
+    assert!(edited.matches("/root/project/a.rs", false));
+    assert!(!matcher.matches("/root/project/a.rs", false));
+    assert!(!clone.matches("/root/project/a.rs", false));
+    assert_eq!((matcher.len(), clone.len(), edited.len()), (4, 4, 5));
+
+    // Descending in one clone compiles state shared with the others.
+    let state = clone.dir_state("/root/project/build");
+    assert!(state.is_match());
+    assert!(matcher.descend(&state, "x.o", false).is_match());
+}
+
+#[test]
+fn test_interned_roots() {
+    let document = Document::parse("*.o\n!keep.o\nbuild/\n", "/root/project").unwrap();
+    let roots: Vec<&str> = document.patterns().map(|p| p.root()).collect();
+    assert_eq!(roots, ["/root/project"; 3]);
+    assert!(roots.iter().all(|root| std::ptr::eq(*root, roots[0])));
+
+    let (shared, mut document) = (roots[0].as_ptr(), document.clone());
+    document.push("/target").unwrap();
+    assert_eq!(document.patterns().last().unwrap().root().as_ptr(), shared);
+
+    // Roots of separately created patterns are shared once compiled.
+    let set = PatternSet::from(["a", "b"].map(|p| Pattern::new(p, "/r").unwrap()));
+    assert!(!std::ptr::eq(set[0].root(), set[1].root()));
+    let matcher = set.into_matcher().unwrap();
+    assert!(std::ptr::eq(matcher[0].root(), matcher[1].root()));
+}
+
+#[test]
+fn test_shared_across_threads() {
+    let matcher = Arc::new(matcher());
+    let paths = ["/root/project/a.o", "/root/project/keep.o", "/root/project/target", "/root/x.o"];
+    let expected: Vec<bool> = paths.iter().map(|p| matcher.matches(p, false)).collect();
+    assert_eq!(expected, [true, false, true, false]);
+
+    let handles: Vec<_> = (0..4).map(|_| {
+        let (shared, cloned) = (matcher.clone(), (*matcher).clone());
+        std::thread::spawn(move || {
+            let state = cloned.dir_state("/root/project");
+            assert!(cloned.descend(&state, "a.o", false).is_match());
+            paths.map(|p| shared.matches(p, false) && cloned.matches(p, false))
+        })
+    }).collect();
+
+    for handle in handles {
+        assert_eq!(handle.join().unwrap().to_vec(), expected);
+    }
+}
@@ -0,0 +1,43 @@
+use gitfilter::{CachedMatcher, Formatter, PathExt, PatternSet};
+
+fn suggest(ignored: &[&str], tracked: &[&str]) -> Vec<String> {
+    let suggestion = PatternSet::suggest(ignored, tracked).unwrap();
+    let lines = suggestion.iter().map(|p| Formatter::new().format_pattern(p)).collect();
+    let matcher = CachedMatcher::new(suggestion.into_matcher().unwrap(), 16);
+    let excluded = |path: &str| matcher.matches(path.trim_end_matches('/'), path.has_trailing_slash());
+    for path in ignored {
+        assert!(excluded(path), "{:?} not ignored", path);
+    }
+
+    for path in tracked {
+        assert!(!excluded(path), "{:?} ignored", path);
+    }
+
+    lines
+}
+
+#[test]
+fn test_suggest() {
+    assert_eq!(suggest(&["a.o", "src/b.o", "src/x/c.o"], &["a.c", "src/b.c"]), ["*.o"]);
+    assert_eq!(suggest(&["a.o", "b.pyc"], &["a.c"]), ["*.o", "*.pyc"]);
+    assert_eq!(suggest(&["a.o", "src/b.o"], &["keep.o"]), ["a.o", "b.o"]);
+    assert_eq!(
+        suggest(&["target/debug/a", "target/release/b.d", "target/x.json"], &["x.json", "a"]),
+        ["target/"]
+    );
+
+    assert_eq!(suggest(&["web/target/a", "target/b"], &["web/b"]), ["target/"]);
+    assert_eq!(suggest(&["cache/", "x/cache/"], &["cache.rs", "y/cache"]), ["cache/"]);
+    assert_eq!(suggest(&["a/build/x", "b/build/x"], &["c/build/x"]), ["a/", "b/"]);
+    assert_eq!(suggest(&["*weird[name]", "!bang"], &["weird"]), [r"[*]weird[[]name[]]", r"\!bang"]);
+    assert!(suggest(&[], &["a"]).is_empty());
+}
+
+#[test]
+fn test_suggest_inseparable() {
+    assert!(PatternSet::suggest(["a.o"], ["a.o"]).is_err());
+    assert!(PatternSet::suggest(["x/a.o"], ["x/a.o", "a.o"]).is_err());
+
+    // Nothing below an ignored directory can stay tracked.
+    assert!(PatternSet::suggest(["build/"], ["build/keep"]).is_err());
+}
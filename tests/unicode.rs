@@ -0,0 +1,69 @@
+#![cfg(feature = "unicode-normalization")]
+
+mod common;
+
+use std::fs;
+
+use gitfilter::{Document, Matcher, Pattern};
+
+use common::{write, TempDir};
+
+const NFC: &str = "caf\u{e9}";
+const NFD: &str = "cafe\u{301}";
+
+fn matcher(text: &str, precompose: bool) -> Matcher {
+    let mut set = Document::parse(text, "").unwrap().to_pattern_set();
+    set.precompose_unicode(precompose);
+    set.into_matcher().unwrap()
+}
+
+#[test]
+fn test_precompose_unicode() {
+    for (pattern, path) in [(NFC, NFD), (NFD, NFC), (NFC, NFC), (NFD, NFD)] {
+        let text = format!("{}/\n*.{}\n!{}/keep\n", pattern, pattern, pattern);
+        let matcher = matcher(&text, true);
+        assert!(matcher.precomposes_unicode());
+        assert!(matcher.matches(path, true), "{:?} {:?}", pattern, path);
+        assert!(matcher.matches(format!("x.{}", path), false));
+        assert!(matcher.matches_bytes(format!("a/{}", path).as_bytes(), true));
+        assert!(!matcher.matches(format!("{}/keep", path), false));
+        assert!(!matcher.matches(path, false));
+
+        let state = matcher.dir_state(format!("a/{}", path));
+        assert!(state.is_match());
+        assert!(!matcher.descend(&state, "keep", false).is_match());
+        assert!(matcher.descend(&matcher.dir_state("a"), path, true).is_match());
+
+        // Without precomposition, only the same composition matches.
+        let matcher = self::matcher(&text, false);
+        assert!(!matcher.precomposes_unicode());
+        assert_eq!(matcher.matches(path, true), pattern == path);
+    }
+
+    let mut matcher = matcher("", true);
+    let id = matcher.push(Pattern::new(NFD, "/r\u{e9}sum\u{e9}").unwrap()).unwrap();
+    assert_eq!(matcher.get(id).unwrap().glob(), "/r\u{e9}sum\u{e9}/**/caf\u{e9}");
+    assert!(matcher.matches("/re\u{301}sume\u{301}/cafe\u{301}", false));
+    let matcher = matcher.into_pattern_set().into_matcher().unwrap();
+    assert!(matcher.matches("/r\u{e9}sume\u{301}/x/cafe\u{301}", false));
+
+    assert_eq!(Pattern::new(NFD, "").unwrap().precomposed().unwrap().glob(), "**/caf\u{e9}");
+    assert!(Pattern::new("[\u{2000}-\u{212b}]", "").unwrap().precomposed().is_err());
+}
+
+#[test]
+fn test_decomposed_files() {
+    let root = TempDir::new("unicode");
+    write(&root, &format!("{}/{}.txt", NFD, NFD), "");
+    write(&root, "other.txt", "");
+
+    let matcher = matcher(&format!("/{}/{}.txt\n", NFC, NFC), true);
+    let dir = fs::read_dir(&root).unwrap().map(|e| e.unwrap().file_name()).find(|n| n != "other.txt").unwrap();
+    let file = fs::read_dir(root.join(&dir)).unwrap().next().unwrap().unwrap().file_name();
+    let path = std::path::Path::new(&dir).join(&file);
+    assert!(matcher.matches(&path, false));
+    assert!(!matcher.matches("other.txt", false));
+
+    let state = matcher.descend(&matcher.dir_state(""), &dir, true);
+    assert!(matcher.descend(&state, &file, false).is_match());
+}
@@ -0,0 +1,128 @@
+use gitfilter::{Matcher, Pattern, PatternSet};
+
+/// A small deterministic pseudo-random number generator.
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((self.0 >> 33) as usize) % n
+    }
+}
+
+fn pattern(rng: &mut Rng) -> Pattern {
+    let i = rng.below(20);
+    let text = match rng.below(8) {
+        0 => format!("*.e{}", i),
+        1 => format!("name{}", i),
+        2 => format!("/dir{}", i),
+        3 => format!("build{}/*.o", i),
+        4 => format!("!name{}", i),
+        5 => format!("p{}*", i),
+        6 => format!("a/**/x{}/", i),
+        _ => format!("!*.e{}", i),
+    };
+
+    let root = ["", "", "", "", "/r", "/r/s", "/q"][rng.below(7)];
+    Pattern::new(&text, root).unwrap()
+}
+
+fn paths() -> Vec<String> {
+    let mut paths = vec![];
+    for root in ["", "/r/", "/r/s/", "/q/", "/z/"] {
+        for i in 0..20 {
+            for path in ["f.e{}", "name{}", "dir{}", "build{}/m.o", "p{}q", "a/b/x{}", "c/name{}"] {
+                paths.push(format!("{}{}", root, path.replace("{}", &i.to_string())));
+            }
+        }
+    }
+
+    paths
+}
+
+fn assert_same(edited: &Matcher, patterns: &[Pattern], paths: &[String]) {
+    let rebuilt = PatternSet::from(patterns.to_vec()).into_matcher().unwrap();
+    assert_eq!(edited.to_string(), rebuilt.to_string());
+    for path in paths {
+        for is_dir in [false, true] {
+            let edited: Vec<_> = edited.find_matches(path, is_dir).map(|m| m.pattern().to_string()).collect();
+            let rebuilt: Vec<_> = rebuilt.find_matches(path, is_dir).map(|m| m.pattern().to_string()).collect();
+            assert_eq!(edited, rebuilt, "{:?}", path);
+        }
+    }
+}
+
+#[test]
+fn test_edits_match_rebuild() {
+    let (mut rng, paths) = (Rng(7), paths());
+    let mut patterns: Vec<Pattern> = (0..1000).map(|_| pattern(&mut rng)).collect();
+    let mut matcher = PatternSet::from(patterns.clone()).into_matcher().unwrap();
+    for round in 0..300 {
+        match rng.below(3) {
+            0 => {
+                let (index, pattern) = (rng.below(patterns.len() + 1), pattern(&mut rng));
+                matcher.insert(index, pattern.clone()).unwrap();
+                patterns.insert(index, pattern);
+            }
+            1 if !patterns.is_empty() => {
+                let index = rng.below(patterns.len());
+                let id = matcher.id(index).unwrap();
+                let removed = matcher.remove(id).unwrap().unwrap();
+                assert_eq!(removed.to_string(), patterns.remove(index).to_string());
+            }
+            _ if !patterns.is_empty() => {
+                let (index, pattern) = (rng.below(patterns.len()), pattern(&mut rng));
+                let id = matcher.id(index).unwrap();
+                matcher.replace(id, pattern.clone()).unwrap();
+                patterns[index] = pattern;
+                assert_eq!(matcher.id(index), Some(id));
+            }
+            _ => {}
+        }
+
+        if round % 25 == 0 {
+            assert_same(&matcher, &patterns, &paths);
+        }
+    }
+
+    assert_same(&matcher, &patterns, &paths);
+    while let Some(id) = matcher.id(0) {
+        matcher.remove(id).unwrap();
+    }
+
+    assert!(!matcher.matches("name1", false));
+    assert_same(&matcher, &[], &paths);
+}
+
+#[test]
+fn test_pattern_ids() {
+    let mut matcher = ["*.o", "!keep.o", "target/"].iter()
+        .map(|p| p.parse::<Pattern>().unwrap())
+        .collect::<Result<Matcher, _>>()
+        .unwrap();
+
+    let (o, keep, target) = (matcher.id(0).unwrap(), matcher.id(1).unwrap(), matcher.id(2).unwrap());
+    assert!(!matcher.matches("keep.o", false));
+
+    let a = matcher.insert(0, "*.a".parse().unwrap()).unwrap();
+    assert_eq!(matcher.position(keep), Some(2));
+    assert_eq!(matcher.position(a), Some(0));
+    assert!(matcher.matches("x.a", false));
+
+    let removed = matcher.remove(keep).unwrap().unwrap();
+    assert_eq!(removed.to_string(), "!**/keep.o");
+    assert_eq!(matcher.remove(keep).unwrap().map(|p| p.to_string()), None);
+    assert!(matcher.matches("keep.o", false));
+    assert_eq!(matcher.position(target), Some(2));
+
+    let replaced = matcher.replace(o, "!*.o".parse().unwrap()).unwrap().unwrap();
+    assert_eq!(replaced.to_string(), "**/*.o");
+    assert_eq!(matcher.get(o).unwrap().to_string(), "!**/*.o");
+    assert!(!matcher.matches("x.o", false));
+    assert!(matcher.replace(keep, "x".parse().unwrap()).unwrap().is_none());
+
+    let pushed = matcher.push("x.o".parse().unwrap()).unwrap();
+    assert!(pushed != o && pushed != a);
+    assert!(matcher.matches("x.o", false));
+    assert_eq!(matcher.to_string(), "[**/*.a, !**/*.o, **/target/, **/x.o]");
+}